@@ -0,0 +1,44 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Build details reported by `/version`. `GIT_SHA` can be given from outside for builds
+/// made without the repository, as the container ones
+fn main() {
+	let git_sha = std::env::var("GIT_SHA")
+		.ok()
+		.or_else(|| output("git", &["rev-parse", "--short=12", "HEAD"]));
+
+	let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+
+	let built_at = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|elapsed| elapsed.as_secs())
+		.unwrap_or(0);
+
+	println!(
+		"cargo:rustc-env=BUILD_GIT_SHA={}",
+		git_sha.unwrap_or_else(|| "unknown".to_owned())
+	);
+	println!(
+		"cargo:rustc-env=BUILD_RUSTC={}",
+		output(&rustc, &["--version"]).unwrap_or_default()
+	);
+	println!("cargo:rustc-env=BUILD_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+	println!("cargo:rustc-env=BUILD_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+	println!("cargo:rustc-env=BUILD_TIMESTAMP={}", built_at);
+
+	println!("cargo:rerun-if-env-changed=GIT_SHA");
+	println!("cargo:rerun-if-changed=.git/HEAD");
+	println!("cargo:rerun-if-changed=.git/refs/heads");
+	println!("cargo:rerun-if-changed=src");
+}
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+	let output = Command::new(program).args(args).output().ok()?;
+	if !output.status.success() {
+		return None;
+	}
+
+	let text = String::from_utf8(output.stdout).ok()?;
+	Some(text.trim().to_owned())
+}
@@ -0,0 +1,428 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+
+use super::handlers::{audit, centers, consistency, projects, users, webhooks};
+
+use super::models::audit::{AuditFilter, AuditToSend};
+use super::models::centers::{
+	CenterChanges, CenterFilter, CenterToSend, StaffRole, StaffToSend,
+};
+use super::models::page::Page;
+use super::models::projects::{
+	NewProject, ProjectChanges, ProjectCreated, ProjectFilter, ProjectToSend,
+};
+use super::models::users::{
+	PasswordReset, StateChangeToSend, UserFilter, UserProject, UserStateChange, UserToSend,
+};
+use super::models::webhooks::{
+	DeadFilter, DeadToSend, NewWebhook, WebhookChanges, WebhookCreated, WebhookToSend,
+};
+
+use crate::app::providers::models::record::parse_key;
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::auth::roles::{Admin, RequireRole};
+use crate::app::providers::services::consistency::check::Report;
+
+pub fn routes() -> Vec<rocket::Route> {
+	routes![
+		list_users,
+		get_user,
+		disable_user,
+		enable_user,
+		delete_user,
+		reset_password,
+		change_project,
+		change_state,
+		user_states,
+		list_projects,
+		get_project,
+		create_project,
+		update_project,
+		list_centers,
+		get_center,
+		create_center,
+		rename_center,
+		delete_center,
+		center_projects,
+		center_staff,
+		assign_staff,
+		remove_staff,
+		consistency_report,
+		consistency_fix,
+		list_audit,
+		list_webhooks,
+		create_webhook,
+		update_webhook,
+		delete_webhook,
+		ping_webhook,
+		dead_deliveries,
+		retry_delivery,
+	]
+}
+
+#[get("/users?<filter..>")]
+async fn list_users(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	filter: UserFilter,
+) -> Result<Json<Page<UserToSend>>, Status> {
+	let page = users::list(db, filter).await?;
+
+	Ok(Json(page))
+}
+
+#[get("/users/<id>")]
+async fn get_user(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<UserToSend>, Status> {
+	let user = users::get(db, id).await?;
+
+	Ok(Json(user))
+}
+
+#[post("/users/<id>/disable")]
+async fn disable_user(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<UserToSend>, Status> {
+	let user = users::set_disabled(db, &admin.actor(), id, true).await?;
+
+	Ok(Json(user))
+}
+
+#[post("/users/<id>/enable")]
+async fn enable_user(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<UserToSend>, Status> {
+	let user = users::set_disabled(db, &admin.actor(), id, false).await?;
+
+	Ok(Json(user))
+}
+
+#[delete("/users/<id>")]
+async fn delete_user(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Status, Status> {
+	users::delete(db, &admin.actor(), id).await?;
+
+	Ok(Status::NoContent)
+}
+
+#[post("/users/<id>/password-reset")]
+async fn reset_password(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<PasswordReset>, Status> {
+	let reset = users::reset_password(db, &admin.actor(), id).await?;
+
+	Ok(Json(reset))
+}
+
+#[put("/users/<id>/project", data = "<project>")]
+async fn change_project(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	project: Json<UserProject>,
+) -> Result<Json<UserToSend>, Status> {
+	let user = users::change_project(db, &admin.actor(), id, &project.project).await?;
+
+	Ok(Json(user))
+}
+
+#[put("/users/<id>/state", data = "<change>")]
+async fn change_state(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	change: Json<UserStateChange>,
+) -> Result<Json<UserToSend>, Status> {
+	let user = users::change_state(db, &admin.actor(), id, change.into_inner()).await?;
+
+	Ok(Json(user))
+}
+
+#[get("/users/<id>/states")]
+async fn user_states(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<Vec<StateChangeToSend>>, Status> {
+	let states = users::states(db, id).await?;
+
+	Ok(Json(states))
+}
+
+#[get("/projects?<filter..>")]
+async fn list_projects(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	filter: ProjectFilter,
+) -> Result<Json<Page<ProjectToSend>>, Status> {
+	let page = projects::list(db, filter).await?;
+
+	Ok(Json(page))
+}
+
+#[get("/projects/<id>")]
+async fn get_project(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<ProjectToSend>, Status> {
+	let project = projects::get(db, id).await?;
+
+	Ok(Json(project))
+}
+
+#[post("/projects", data = "<project>")]
+async fn create_project(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	project: Json<NewProject>,
+) -> Result<Json<ProjectCreated>, Status> {
+	let project = projects::create(db, &admin.actor(), project.into_inner()).await?;
+
+	Ok(Json(project))
+}
+
+#[patch("/projects/<id>", data = "<changes>")]
+async fn update_project(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	changes: Json<ProjectChanges>,
+) -> Result<Json<ProjectToSend>, Status> {
+	let project = projects::update(db, &admin.actor(), id, changes.into_inner()).await?;
+
+	Ok(Json(project))
+}
+
+#[get("/centers?<filter..>")]
+async fn list_centers(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	filter: CenterFilter,
+) -> Result<Json<Page<CenterToSend>>, Status> {
+	let page = centers::list(db, filter).await?;
+
+	Ok(Json(page))
+}
+
+#[get("/centers/<id>")]
+async fn get_center(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<CenterToSend>, Status> {
+	let center = centers::get(db, id).await?;
+
+	Ok(Json(center))
+}
+
+#[post("/centers", data = "<center>")]
+async fn create_center(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	center: Json<CenterChanges>,
+) -> Result<Json<CenterToSend>, Status> {
+	let center = centers::create(db, &admin.actor(), &center.name).await?;
+
+	Ok(Json(center))
+}
+
+#[patch("/centers/<id>", data = "<changes>")]
+async fn rename_center(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	changes: Json<CenterChanges>,
+) -> Result<Json<CenterToSend>, Status> {
+	let center = centers::rename(db, &admin.actor(), id, &changes.name).await?;
+
+	Ok(Json(center))
+}
+
+#[delete("/centers/<id>")]
+async fn delete_center(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Status, Status> {
+	centers::delete(db, &admin.actor(), id).await?;
+
+	Ok(Status::NoContent)
+}
+
+#[get("/centers/<id>/projects?<page>&<per_page>")]
+async fn center_projects(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+	page: Option<u32>,
+	per_page: Option<u32>,
+) -> Result<Json<Page<ProjectToSend>>, Status> {
+	centers::get(db, id).await?;
+
+	let filter = ProjectFilter {
+		page,
+		per_page,
+		center: Some(parse_key(id, "centers")?.to_string()),
+	};
+	let page = projects::list(db, filter).await?;
+
+	Ok(Json(page))
+}
+
+#[get("/centers/<id>/staff")]
+async fn center_staff(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Json<Vec<StaffToSend>>, Status> {
+	let staff = centers::staff(db, id).await?;
+
+	Ok(Json(staff))
+}
+
+#[put("/centers/<id>/staff/<user>", data = "<role>")]
+async fn assign_staff(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	user: &str,
+	role: Json<StaffRole>,
+) -> Result<Json<Vec<StaffToSend>>, Status> {
+	let staff = centers::assign_staff(db, &admin.actor(), id, user, role.role).await?;
+
+	Ok(Json(staff))
+}
+
+#[delete("/centers/<id>/staff/<user>")]
+async fn remove_staff(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	user: &str,
+) -> Result<Status, Status> {
+	centers::remove_staff(db, &admin.actor(), id, user).await?;
+
+	Ok(Status::NoContent)
+}
+
+#[get("/consistency")]
+async fn consistency_report(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+) -> Result<Json<Report>, Status> {
+	let report = consistency::report(db).await?;
+
+	Ok(Json(report))
+}
+
+#[post("/consistency/fix")]
+async fn consistency_fix(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+) -> Result<Json<Report>, Status> {
+	let report = consistency::fix(db, &admin.actor()).await?;
+
+	Ok(Json(report))
+}
+
+#[get("/audit?<filter..>")]
+async fn list_audit(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	filter: AuditFilter,
+) -> Result<Json<Page<AuditToSend>>, Status> {
+	let page = audit::list(db, filter).await?;
+
+	Ok(Json(page))
+}
+
+#[get("/webhooks")]
+async fn list_webhooks(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+) -> Result<Json<Vec<WebhookToSend>>, Status> {
+	let webhooks = webhooks::list(db).await?;
+
+	Ok(Json(webhooks))
+}
+
+#[post("/webhooks", data = "<webhook>")]
+async fn create_webhook(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	webhook: Json<NewWebhook>,
+) -> Result<Json<WebhookCreated>, Status> {
+	let webhook = webhooks::create(db, &admin.actor(), webhook.into_inner()).await?;
+
+	Ok(Json(webhook))
+}
+
+#[patch("/webhooks/<id>", data = "<changes>")]
+async fn update_webhook(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+	changes: Json<WebhookChanges>,
+) -> Result<Json<WebhookToSend>, Status> {
+	let webhook = webhooks::update(db, &admin.actor(), id, changes.into_inner()).await?;
+
+	Ok(Json(webhook))
+}
+
+#[delete("/webhooks/<id>")]
+async fn delete_webhook(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Status, Status> {
+	webhooks::delete(db, &admin.actor(), id).await?;
+
+	Ok(Status::NoContent)
+}
+
+#[post("/webhooks/<id>/ping")]
+async fn ping_webhook(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Status, Status> {
+	webhooks::ping(db, id).await?;
+
+	Ok(Status::Accepted)
+}
+
+#[get("/webhooks/dead?<filter..>")]
+async fn dead_deliveries(
+	db: &State<DbAuth>,
+	_admin: RequireRole<Admin>,
+	filter: DeadFilter,
+) -> Result<Json<Page<DeadToSend>>, Status> {
+	let page = webhooks::dead(db, filter).await?;
+
+	Ok(Json(page))
+}
+
+#[post("/webhooks/dead/<id>/retry")]
+async fn retry_delivery(
+	db: &State<DbAuth>,
+	admin: RequireRole<Admin>,
+	id: &str,
+) -> Result<Status, Status> {
+	webhooks::retry(db, &admin.actor(), id).await?;
+
+	Ok(Status::Accepted)
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use surrealdb::sql::Datetime;
+use tracing::error;
+
+use crate::app::modules::admin::models::audit::{AuditFilter, AuditRow, AuditToSend};
+use crate::app::modules::admin::models::page::{paging, Page};
+
+use crate::app::providers::models::record::parse_optional;
+use crate::app::providers::services::auth::db::DbAuth;
+
+const AUDIT_FILTER: &str = r#"
+	($b_user = NONE OR actor = $b_user OR target = $b_user)
+	AND ($b_project = NONE OR project = $b_project)
+	AND ($b_action = NONE OR string::startsWith(action, $b_action))
+	AND ($b_outcome = NONE OR (outcome ?? 'success') = $b_outcome)
+	AND ($b_from = NONE OR at >= $b_from)
+	AND ($b_to = NONE OR at < $b_to)
+"#;
+
+/// Newest entries first
+pub async fn list(db: &DbAuth, filter: AuditFilter) -> Result<Page<AuditToSend>, Status> {
+	let (page, per_page) = paging(filter.page, filter.per_page);
+
+	let user = parse_optional(filter.user.as_deref(), "users")?;
+	let project = parse_optional(filter.project.as_deref(), "projects")?;
+	let from = parse_time(filter.from.as_deref())?;
+	let to = parse_time(filter.to.as_deref())?;
+	if !matches!(filter.outcome.as_deref(), None | Some("success") | Some("failure")) {
+		return Err(Status::BadRequest);
+	}
+
+	let sql = format!(
+		r#"
+		RETURN count(SELECT id FROM audit WHERE {AUDIT_FILTER});
+		RETURN SELECT * FROM audit WHERE {AUDIT_FILTER}
+			ORDER BY at DESC LIMIT $b_limit START $b_start;
+		"#
+	);
+
+	let mut query = db
+		.read("audit.list", sql)
+		.bind(("b_user", user))
+		.bind(("b_project", project))
+		.bind(("b_action", filter.action))
+		.bind(("b_outcome", filter.outcome))
+		.bind(("b_from", from))
+		.bind(("b_to", to))
+		.bind(("b_limit", per_page))
+		.bind(("b_start", (page - 1) * per_page))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying audit");
+			Status::InternalServerError
+		})?;
+
+	let entries: Vec<AuditRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting audit entries");
+		Status::InternalServerError
+	})?;
+
+	let total: Option<u64> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error counting audit entries");
+		Status::InternalServerError
+	})?;
+
+	Ok(Page {
+		items: entries.into_iter().map(AuditToSend::from).collect(),
+		page,
+		per_page,
+		total: total.unwrap_or(0),
+	})
+}
+
+fn parse_time(time: Option<&str>) -> Result<Option<Datetime>, Status> {
+	time.map(|time| {
+		DateTime::parse_from_rfc3339(time)
+			.map(|time| Datetime::from(time.with_timezone(&Utc)))
+			.map_err(|_| Status::BadRequest)
+	})
+	.transpose()
+}
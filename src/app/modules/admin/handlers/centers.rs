@@ -0,0 +1,328 @@
+use rocket::http::Status;
+use rocket::serde::json::json;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::modules::admin::models::centers::{
+	CenterFilter, CenterRow, CenterToSend, StaffRow, StaffToSend,
+};
+use crate::app::modules::admin::models::page::{paging, Page};
+
+use crate::app::providers::models::center::Center;
+use crate::app::providers::models::record::parse_key;
+use crate::app::providers::models::user::Role;
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::access::check_errors;
+use crate::app::providers::services::auth::db::DbAuth;
+
+const CENTER_FIELDS: &str = r#"
+	id, name,
+	count(<-belongs) AS projects,
+	count(<-roled[WHERE role NOTINSIDE ['parti', 'guest']]) AS staff
+"#;
+
+pub async fn list(db: &DbAuth, filter: CenterFilter) -> Result<Page<CenterToSend>, Status> {
+	let (page, per_page) = paging(filter.page, filter.per_page);
+
+	let sql = format!(
+		r#"
+		RETURN count(SELECT id FROM centers);
+		RETURN SELECT {CENTER_FIELDS} FROM centers ORDER BY name LIMIT $b_limit START $b_start;
+		"#
+	);
+
+	let mut query = db
+		.read("centers.list", sql)
+		.bind(("b_limit", per_page))
+		.bind(("b_start", (page - 1) * per_page))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying centers");
+			Status::InternalServerError
+		})?;
+
+	let centers: Vec<CenterRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting centers");
+		Status::InternalServerError
+	})?;
+
+	let total: Option<u64> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error counting centers");
+		Status::InternalServerError
+	})?;
+
+	Ok(Page {
+		items: centers.into_iter().map(CenterToSend::from).collect(),
+		page,
+		per_page,
+		total: total.unwrap_or(0),
+	})
+}
+
+pub async fn get(db: &DbAuth, id: &str) -> Result<CenterToSend, Status> {
+	fetch(db, &parse_key(id, "centers")?).await
+}
+
+pub async fn create(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	name: &str,
+) -> Result<CenterToSend, Status> {
+	let mut query = db
+		.query(
+			"centers.create",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF array::len(SELECT id FROM centers WHERE name = $b_name) > 0 {
+				THROW "E_CONFLICT";
+			};
+
+			LET $q_center = (CREATE ONLY centers CONTENT { name: $b_name });
+
+			COMMIT TRANSACTION;
+
+			RETURN $q_center;
+			"#,
+		)
+		.bind(("b_name", name))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error creating center");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let center: Option<Center> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting center");
+		Status::InternalServerError
+	})?;
+
+	let center = center.ok_or(Status::InternalServerError)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "center.create", &center.id).detail(json!({ "name": name })),
+	)
+	.await;
+
+	fetch(db, &center.id).await
+}
+
+pub async fn rename(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	name: &str,
+) -> Result<CenterToSend, Status> {
+	let id = parse_key(id, "centers")?;
+
+	let mut query = db
+		.query(
+			"centers.rename",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF !(SELECT VALUE id FROM ONLY $b_id) {
+				THROW "E_NOT_FOUND";
+			};
+
+			IF array::len(SELECT id FROM centers WHERE name = $b_name AND id != $b_id) > 0 {
+				THROW "E_CONFLICT";
+			};
+
+			UPDATE $b_id SET name = $b_name WHERE id;
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.bind(("b_name", name))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error renaming center");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "center.rename", &id).detail(json!({ "name": name })),
+	)
+	.await;
+
+	fetch(db, &id).await
+}
+
+/// Only centers without projects can go, their staff roles go with them
+pub async fn delete(db: &DbAuth, actor: &Actor<'_>, id: &str) -> Result<(), Status> {
+	let id = parse_key(id, "centers")?;
+
+	let mut query = db
+		.query(
+			"centers.delete",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF !(SELECT VALUE id FROM ONLY $b_id) {
+				THROW "E_NOT_FOUND";
+			};
+
+			IF count($b_id<-belongs) > 0 {
+				THROW "E_CONFLICT";
+			};
+
+			DELETE $b_id;
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error deleting center");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(db, AuditEntry::new(actor, "center.delete", &id)).await;
+
+	Ok(())
+}
+
+/// Everyone holding a role in the center other than participants and guests
+pub async fn staff(db: &DbAuth, id: &str) -> Result<Vec<StaffToSend>, Status> {
+	let id = parse_key(id, "centers")?;
+	fetch(db, &id).await?;
+
+	let mut query = db
+		.read(
+			"centers.staff",
+			r#"
+			RETURN SELECT in AS id, in.username AS username, role FROM roled
+				WHERE out = $b_id AND role NOTINSIDE ['parti', 'guest'] ORDER BY username;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying staff");
+			Status::InternalServerError
+		})?;
+
+	let staff: Vec<StaffRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting staff");
+		Status::InternalServerError
+	})?;
+
+	Ok(staff.into_iter().map(StaffToSend::from).collect())
+}
+
+/// Gives `user` the role in the center, replacing the one held before
+pub async fn assign_staff(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	user: &str,
+	role: Role,
+) -> Result<Vec<StaffToSend>, Status> {
+	let center = parse_key(id, "centers")?;
+	let user = parse_key(user, "users")?;
+
+	let mut query = db
+		.query(
+			"centers.assign_staff",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF !(SELECT VALUE id FROM ONLY $b_center) OR !(SELECT VALUE id FROM ONLY $b_user) {
+				THROW "E_NOT_FOUND";
+			};
+
+			DELETE roled WHERE in = $b_user AND out = $b_center;
+			RELATE $b_user->roled->$b_center SET role = $b_role;
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_center", &center))
+		.bind(("b_user", &user))
+		.bind(("b_role", role))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error assigning role");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "center.staff.assign", &center)
+			.detail(json!({ "user": user.to_string(), "role": role })),
+	)
+	.await;
+
+	staff(db, id).await
+}
+
+pub async fn remove_staff(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	user: &str,
+) -> Result<(), Status> {
+	let center = parse_key(id, "centers")?;
+	let user = parse_key(user, "users")?;
+
+	let mut query = db
+		.query(
+			"centers.remove_staff",
+			"RETURN DELETE roled WHERE in = $b_user AND out = $b_center RETURN BEFORE;",
+		)
+		.bind(("b_center", &center))
+		.bind(("b_user", &user))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error removing role");
+			Status::InternalServerError
+		})?;
+
+	let removed: Vec<Thing> = query.take((query.num_statements() - 1, "id")).map_err(|e| {
+		error!(error = %e, "Error getting role");
+		Status::InternalServerError
+	})?;
+
+	if removed.is_empty() {
+		return Err(Status::NotFound);
+	}
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "center.staff.remove", &center)
+			.detail(json!({ "user": user.to_string() })),
+	)
+	.await;
+
+	Ok(())
+}
+
+async fn fetch(db: &DbAuth, id: &Thing) -> Result<CenterToSend, Status> {
+	let sql = format!("RETURN SELECT {CENTER_FIELDS} FROM ONLY $b_id;");
+
+	let mut query = db.query("centers.fetch", sql).bind(("b_id", id)).await.map_err(|e| {
+		error!(error = %e, "Error querying center");
+		Status::InternalServerError
+	})?;
+
+	let center: Option<CenterRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting center");
+		Status::InternalServerError
+	})?;
+
+	center.map(CenterToSend::from).ok_or(Status::NotFound)
+}
@@ -0,0 +1,38 @@
+use rocket::http::Status;
+use rocket::serde::json::json;
+use tracing::error;
+
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::consistency::check::{self, Report};
+
+pub async fn report(db: &DbAuth) -> Result<Report, Status> {
+	check::report(db).await.map_err(|e| {
+		error!(error = %e, "Error checking consistency");
+		Status::InternalServerError
+	})
+}
+
+pub async fn fix(db: &DbAuth, actor: &Actor<'_>) -> Result<Report, Status> {
+	let report = check::fix(db).await.map_err(|e| {
+		error!(error = %e, "Error fixing consistency");
+		Status::InternalServerError
+	})?;
+
+	if report.fixed {
+		trail::record(
+			db,
+			AuditEntry::event("consistency.fix", actor.meta)
+				.actor(actor.claims.id.parse().ok())
+				.detail(json!({
+					"missing_join": report.missing_join,
+					"missing_roled": report.missing_roled,
+					"unresolved": report.unresolved,
+				})),
+		)
+		.await;
+	}
+
+	Ok(report)
+}
@@ -0,0 +1,220 @@
+use std::borrow::Cow;
+
+use rocket::http::Status;
+use rocket::serde::json::json;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::modules::admin::models::page::{paging, Page};
+use crate::app::modules::admin::models::projects::{
+	NewProject, ProjectChanges, ProjectCreated, ProjectFilter, ProjectRow, ProjectToSend,
+};
+
+use crate::app::providers::models::project::ProjectState;
+use crate::app::providers::models::record::{parse_key, parse_optional, parse_record};
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::access::check_errors;
+use crate::app::providers::services::auth::db::DbAuth;
+
+const PROJECT_FIELDS: &str = r#"
+	id, name, state, signup, center,
+	count(<-join[WHERE pending != true]) AS members,
+	count(<-join[WHERE pending = true]) AS pending
+"#;
+
+pub async fn list(db: &DbAuth, filter: ProjectFilter) -> Result<Page<ProjectToSend>, Status> {
+	let (page, per_page) = paging(filter.page, filter.per_page);
+	let center = parse_optional(filter.center.as_deref(), "centers")?;
+
+	let sql = format!(
+		r#"
+		RETURN count(SELECT id FROM projects WHERE $b_center = NONE OR center = $b_center);
+		RETURN SELECT {PROJECT_FIELDS} FROM projects WHERE $b_center = NONE OR center = $b_center
+			ORDER BY name LIMIT $b_limit START $b_start;
+		"#
+	);
+
+	let mut query = db
+		.read("projects.list", sql)
+		.bind(("b_center", center))
+		.bind(("b_limit", per_page))
+		.bind(("b_start", (page - 1) * per_page))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying projects");
+			Status::InternalServerError
+		})?;
+
+	let projects: Vec<ProjectRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting projects");
+		Status::InternalServerError
+	})?;
+
+	let total: Option<u64> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error counting projects");
+		Status::InternalServerError
+	})?;
+
+	Ok(Page {
+		items: projects.into_iter().map(ProjectToSend::from).collect(),
+		page,
+		per_page,
+		total: total.unwrap_or(0),
+	})
+}
+
+pub async fn get(db: &DbAuth, id: &str) -> Result<ProjectToSend, Status> {
+	fetch(db, &parse_key(id, "projects")?).await
+}
+
+/// Creates the project with a fresh secret and links it to its center
+pub async fn create(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	project: NewProject,
+) -> Result<ProjectCreated, Status> {
+	let center = parse_record(&project.center, "centers")?;
+
+	let mut query = db
+		.query(
+			"projects.create",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF !(SELECT VALUE id FROM ONLY $b_center) {
+				THROW "E_NOT_FOUND";
+			};
+
+			IF array::len(SELECT id FROM projects WHERE name = $b_name) > 0 {
+				THROW "E_CONFLICT";
+			};
+
+			LET $q_project = (CREATE ONLY projects CONTENT {
+				name: $b_name,
+				state: $b_state,
+				signup: $b_signup,
+				token: rand::string(48),
+				center: $b_center,
+			});
+			RELATE ($q_project.id)->belongs->$b_center;
+
+			COMMIT TRANSACTION;
+
+			RETURN $q_project;
+			"#,
+		)
+		.bind(("b_center", &center))
+		.bind(("b_name", &project.name))
+		.bind(("b_state", project.state.unwrap_or(ProjectState::Active)))
+		.bind(("b_signup", project.signup.unwrap_or_default()))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error creating project");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let created: Option<CreatedRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting project");
+		Status::InternalServerError
+	})?;
+
+	let created = created.ok_or(Status::InternalServerError)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "project.create", &created.id)
+			.detail(json!({ "name": &created.name, "center": center.to_string() })),
+	)
+	.await;
+
+	let project = fetch(db, &created.id).await?;
+
+	Ok(ProjectCreated {
+		project,
+		token: created.token,
+	})
+}
+
+/// Renames the project, changes its state and/or its signup mode
+pub async fn update(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	changes: ProjectChanges,
+) -> Result<ProjectToSend, Status> {
+	let id = parse_key(id, "projects")?;
+
+	let mut query = db
+		.query(
+			"projects.update",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF !(SELECT VALUE id FROM ONLY $b_id) {
+				THROW "E_NOT_FOUND";
+			};
+
+			IF $b_name AND array::len(SELECT id FROM projects WHERE name = $b_name AND id != $b_id) > 0 {
+				THROW "E_CONFLICT";
+			};
+
+			UPDATE $b_id SET
+				name = $b_name ?? name,
+				state = $b_state ?? state,
+				signup = $b_signup ?? signup
+			WHERE id;
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.bind(("b_name", &changes.name))
+		.bind(("b_state", changes.state))
+		.bind(("b_signup", changes.signup))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error updating project");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "project.update", &id).detail(json!({
+			"name": changes.name,
+			"state": changes.state,
+			"signup": changes.signup,
+		})),
+	)
+	.await;
+
+	fetch(db, &id).await
+}
+
+#[derive(rocket::serde::Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct CreatedRow {
+	id: Thing,
+	name: Cow<'static, str>,
+	token: Cow<'static, str>,
+}
+
+async fn fetch(db: &DbAuth, id: &Thing) -> Result<ProjectToSend, Status> {
+	let sql = format!("RETURN SELECT {PROJECT_FIELDS} FROM ONLY $b_id;");
+
+	let mut query = db.query("projects.fetch", sql).bind(("b_id", id)).await.map_err(|e| {
+		error!(error = %e, "Error querying project");
+		Status::InternalServerError
+	})?;
+
+	let project: Option<ProjectRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting project");
+		Status::InternalServerError
+	})?;
+
+	project.map(ProjectToSend::from).ok_or(Status::NotFound)
+}
@@ -0,0 +1,403 @@
+use std::borrow::Cow;
+
+use rocket::http::Status;
+use rocket::serde::json::json;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::modules::admin::models::page::{paging, Page};
+use crate::app::modules::admin::models::users::{
+	PasswordReset, StateChangeRow, StateChangeToSend, UserFilter, UserRow, UserStateChange,
+	UserToSend,
+};
+
+use crate::app::providers::models::record::{parse_key, parse_optional, parse_record};
+use crate::app::providers::models::user::{Role, UserState};
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::access::check_errors;
+use crate::app::providers::services::auth::db::DbAuth;
+
+/// Selected without `RETURN`, the roles subquery inside one overflows the stack of a worker
+/// thread in debug builds
+const USER_FIELDS: &str = r#"
+	id, username, project, state, disabled, password_reset,
+	(SELECT out AS id, out.name AS name, role FROM roled WHERE in = $parent.id) AS roles,
+	->join[WHERE pending != true]->projects AS projects
+"#;
+
+const USER_FILTER: &str = r#"
+	($b_q = NONE OR string::contains(string::lowercase(username), string::lowercase($b_q)))
+	AND ($b_project = NONE OR ->join[WHERE pending != true].out CONTAINS $b_project)
+	AND (($b_center = NONE AND $b_role = NONE)
+		OR array::len(->roled[WHERE ($b_center = NONE OR out = $b_center) AND ($b_role = NONE OR role = $b_role)]) > 0)
+	AND ($b_state = NONE OR (state ?? 'active') = $b_state)
+"#;
+
+pub async fn list(db: &DbAuth, filter: UserFilter) -> Result<Page<UserToSend>, Status> {
+	let (page, per_page) = paging(filter.page, filter.per_page);
+
+	let project = parse_optional(filter.project.as_deref(), "projects")?;
+	let center = parse_optional(filter.center.as_deref(), "centers")?;
+	let role = match filter.role.as_deref() {
+		Some(role) => Some(role.parse::<Role>().map_err(|_| Status::BadRequest)?),
+		None => None,
+	};
+
+	let sql = format!(
+		r#"
+		RETURN count(SELECT id FROM users WHERE {USER_FILTER});
+		SELECT {USER_FIELDS} FROM users WHERE {USER_FILTER}
+			ORDER BY username LIMIT $b_limit START $b_start;
+		"#
+	);
+
+	let mut query = db
+		.read("users.list", sql)
+		.bind(("b_q", filter.q))
+		.bind(("b_project", project))
+		.bind(("b_center", center))
+		.bind(("b_role", role))
+		.bind(("b_state", filter.state))
+		.bind(("b_limit", per_page))
+		.bind(("b_start", (page - 1) * per_page))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying users");
+			Status::InternalServerError
+		})?;
+
+	let users: Vec<UserRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting users");
+		Status::InternalServerError
+	})?;
+
+	let total: Option<u64> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error counting users");
+		Status::InternalServerError
+	})?;
+
+	Ok(Page {
+		items: users.into_iter().map(UserToSend::from).collect(),
+		page,
+		per_page,
+		total: total.unwrap_or(0),
+	})
+}
+
+pub async fn get(db: &DbAuth, id: &str) -> Result<UserToSend, Status> {
+	fetch(db, &parse_key(id, "users")?).await
+}
+
+/// Disabling the user also closes its sessions, its tokens stop working at once
+pub async fn set_disabled(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	disabled: bool,
+) -> Result<UserToSend, Status> {
+	let id = parse_key(id, "users")?;
+
+	let mut query = db
+		.query(
+			"users.set_disabled",
+			r#"
+			BEGIN TRANSACTION;
+
+			LET $q_updated = (UPDATE $b_id SET disabled = $b_disabled WHERE id);
+			IF $q_updated AND $b_disabled {
+				UPDATE sessions SET revoked = true, revoked_at = time::now(), reason = 'disabled'
+					WHERE user = $b_id AND revoked = false;
+			};
+
+			COMMIT TRANSACTION;
+
+			RETURN $q_updated;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.bind(("b_disabled", disabled))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error updating user");
+			Status::InternalServerError
+		})?;
+
+	let updated: Vec<Thing> = query.take((query.num_statements() - 1, "id")).map_err(|e| {
+		error!(error = %e, "Error getting user");
+		Status::InternalServerError
+	})?;
+
+	if updated.is_empty() {
+		return Err(Status::NotFound);
+	}
+
+	let action = if disabled {
+		"user.disable"
+	} else {
+		"user.enable"
+	};
+	trail::record(db, AuditEntry::new(actor, action, &id)).await;
+
+	fetch(db, &id).await
+}
+
+pub async fn delete(db: &DbAuth, actor: &Actor<'_>, id: &str) -> Result<(), Status> {
+	let id = parse_key(id, "users")?;
+
+	// edges go away with the user, its sessions are closed
+	let mut query = db
+		.query(
+			"users.delete",
+			r#"
+			BEGIN TRANSACTION;
+
+			LET $q_user = (SELECT VALUE id FROM ONLY $b_id);
+			IF $q_user {
+				UPDATE sessions SET revoked = true, revoked_at = time::now(), reason = 'deleted'
+					WHERE user = $q_user AND revoked = false;
+				DELETE $q_user;
+			};
+
+			COMMIT TRANSACTION;
+
+			RETURN $q_user;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error deleting user");
+			Status::InternalServerError
+		})?;
+
+	let deleted: Option<Thing> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting user");
+		Status::InternalServerError
+	})?;
+
+	if deleted.is_none() {
+		return Err(Status::NotFound);
+	}
+
+	trail::record(db, AuditEntry::new(actor, "user.delete", &id)).await;
+
+	Ok(())
+}
+
+/// Replaces the password by a random one the user must change after the next login
+pub async fn reset_password(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+) -> Result<PasswordReset, Status> {
+	let id = parse_key(id, "users")?;
+
+	let mut query = db
+		.query(
+			"users.reset_password",
+			r#"
+			BEGIN TRANSACTION;
+
+			LET $q_password = rand::string(16);
+			LET $q_user = (UPDATE $b_id SET password = $q_password, password_reset = true WHERE id);
+			IF $q_user {
+				UPDATE sessions SET revoked = true, revoked_at = time::now(), reason = 'password_reset'
+					WHERE user = $b_id AND revoked = false;
+			};
+
+			COMMIT TRANSACTION;
+
+			RETURN IF $q_user THEN $q_password END;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error resetting password");
+			Status::InternalServerError
+		})?;
+
+	let password: Option<Cow<'static, str>> =
+		query.take(query.num_statements() - 1).map_err(|e| {
+			error!(error = %e, "Error getting password");
+			Status::InternalServerError
+		})?;
+
+	let password = password.ok_or(Status::NotFound)?;
+
+	trail::record(db, AuditEntry::new(actor, "user.password_reset", &id)).await;
+
+	Ok(PasswordReset {
+		password,
+	})
+}
+
+/// Moves the user to `project`, giving a participant role in its center when missing
+pub async fn change_project(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	project: &str,
+) -> Result<UserToSend, Status> {
+	let id = parse_key(id, "users")?;
+	let project = parse_record(project, "projects")?;
+
+	let mut query = db
+		.query(
+			"users.change_project",
+			r#"
+			BEGIN TRANSACTION;
+
+			LET $q_user = (SELECT VALUE id FROM ONLY $b_id);
+			LET $q_center = (SELECT VALUE (->belongs->centers)[0] FROM ONLY $b_project);
+
+			IF !$q_user OR !$q_center {
+				THROW "E_NOT_FOUND";
+			};
+
+			DELETE join WHERE in = $q_user;
+			RELATE $q_user->join->$b_project;
+			UPDATE $q_user SET project = $b_project;
+
+			IF array::len(SELECT id FROM roled WHERE in = $q_user AND out = $q_center) = 0 {
+				RELATE $q_user->roled->$q_center SET role = 'parti';
+			};
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.bind(("b_project", &project))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error changing project");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(db, AuditEntry::new(actor, "user.project", &id).project(Some(project))).await;
+
+	fetch(db, &id).await
+}
+
+/// Moves the user to another state keeping the history, leaving `active` closes its sessions
+pub async fn change_state(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	change: UserStateChange,
+) -> Result<UserToSend, Status> {
+	let id = parse_key(id, "users")?;
+
+	let mut query = db
+		.query(
+			"users.change_state",
+			r#"
+			BEGIN TRANSACTION;
+
+			LET $q_user = (SELECT id, state ?? 'active' AS state FROM ONLY $b_id);
+			IF !$q_user {
+				THROW "E_NOT_FOUND";
+			};
+
+			IF $q_user.state != $b_state {
+				UPDATE $q_user.id SET state = $b_state;
+				CREATE state_changes CONTENT {
+					user: $q_user.id,
+					previous: $q_user.state,
+					state: $b_state,
+					reason: $b_reason,
+					by: $b_actor,
+					at: time::now(),
+				};
+
+				IF $b_state != 'active' {
+					UPDATE sessions SET revoked = true, revoked_at = time::now(), reason = 'state'
+						WHERE user = $q_user.id AND revoked = false;
+				};
+			};
+
+			COMMIT TRANSACTION;
+
+			RETURN $q_user.state;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.bind(("b_state", change.state))
+		.bind(("b_reason", change.reason))
+		.bind(("b_actor", actor.claims.id.parse::<Thing>().ok()))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error changing user state");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let previous: Option<UserState> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting user state");
+		Status::InternalServerError
+	})?;
+
+	if previous.is_some_and(|previous| previous != change.state) {
+		trail::record(
+			db,
+			AuditEntry::new(actor, "user.state", &id)
+				.detail(json!({ "previous": previous, "state": change.state })),
+		)
+		.await;
+	}
+
+	fetch(db, &id).await
+}
+
+/// Every state the user went through, newest first
+pub async fn states(db: &DbAuth, id: &str) -> Result<Vec<StateChangeToSend>, Status> {
+	let id = parse_key(id, "users")?;
+
+	let mut query = db
+		.read(
+			"users.states",
+			r#"
+			IF !(SELECT VALUE id FROM ONLY $b_id) {
+				THROW "E_NOT_FOUND";
+			};
+
+			RETURN SELECT previous, state, reason, by, at FROM state_changes
+				WHERE user = $b_id ORDER BY at DESC;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying user states");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let changes: Vec<StateChangeRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting user states");
+		Status::InternalServerError
+	})?;
+
+	Ok(changes.into_iter().map(StateChangeToSend::from).collect())
+}
+
+async fn fetch(db: &DbAuth, id: &Thing) -> Result<UserToSend, Status> {
+	let sql = format!("SELECT {USER_FIELDS} FROM ONLY $b_id;");
+
+	let mut query = db.query("users.fetch", sql).bind(("b_id", id)).await.map_err(|e| {
+		error!(error = %e, "Error querying user");
+		Status::InternalServerError
+	})?;
+
+	let user: Option<UserRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting user");
+		Status::InternalServerError
+	})?;
+
+	user.map(UserToSend::from).ok_or(Status::NotFound)
+}
@@ -0,0 +1,318 @@
+use std::borrow::Cow;
+
+use rocket::http::Status;
+use rocket::serde::json::json;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::modules::admin::models::page::{paging, Page};
+use crate::app::modules::admin::models::webhooks::{
+	DeadFilter, DeadRow, DeadToSend, NewWebhook, WebhookChanges, WebhookCreated, WebhookRow,
+	WebhookToSend,
+};
+
+use crate::app::providers::models::record::{parse_key, parse_optional};
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::access::check_errors;
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::webhooks::delivery;
+
+const WEBHOOK_FIELDS: &str = r#"
+	id, url, events, active, created_at,
+	count(SELECT id FROM webhook_deliveries WHERE webhook = $parent.id) AS queued,
+	count(SELECT id FROM webhook_dead WHERE webhook = $parent.id) AS dead
+"#;
+
+pub async fn list(db: &DbAuth) -> Result<Vec<WebhookToSend>, Status> {
+	let sql = format!("RETURN SELECT {WEBHOOK_FIELDS} FROM webhooks ORDER BY created_at;");
+
+	let mut query = db.read("webhooks.list", sql).await.map_err(|e| {
+		error!(error = %e, "Error querying webhooks");
+		Status::InternalServerError
+	})?;
+
+	let webhooks: Vec<WebhookRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting webhooks");
+		Status::InternalServerError
+	})?;
+
+	Ok(webhooks.into_iter().map(WebhookToSend::from).collect())
+}
+
+/// Subscribes `url` to the events, with a random secret unless one is given
+pub async fn create(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	webhook: NewWebhook,
+) -> Result<WebhookCreated, Status> {
+	check_url(&webhook.url)?;
+	check_events(&webhook.events)?;
+
+	let mut query = db
+		.query(
+			"webhooks.create",
+			r#"
+			RETURN CREATE ONLY webhooks CONTENT {
+				url: $b_url,
+				events: $b_events,
+				secret: $b_secret ?? rand::string(40),
+				active: true,
+				created_at: time::now(),
+			} RETURN id, secret;
+			"#,
+		)
+		.bind(("b_url", &webhook.url))
+		.bind(("b_events", &webhook.events))
+		.bind(("b_secret", &webhook.secret))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error creating webhook");
+			Status::InternalServerError
+		})?;
+
+	let created: Option<SecretRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting webhook");
+		Status::InternalServerError
+	})?;
+
+	let created = created.ok_or(Status::InternalServerError)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "webhook.create", &created.id)
+			.detail(json!({ "url": webhook.url, "events": webhook.events })),
+	)
+	.await;
+
+	let webhook = fetch(db, &created.id).await?;
+
+	Ok(WebhookCreated {
+		webhook,
+		secret: created.secret,
+	})
+}
+
+/// Changes the subscription, the fields left out keep their value
+pub async fn update(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	changes: WebhookChanges,
+) -> Result<WebhookToSend, Status> {
+	let id = parse_key(id, "webhooks")?;
+
+	if let Some(url) = &changes.url {
+		check_url(url)?;
+	}
+	if let Some(events) = &changes.events {
+		check_events(events)?;
+	}
+
+	let mut query = db
+		.query(
+			"webhooks.update",
+			r#"
+			BEGIN TRANSACTION;
+
+			IF !(SELECT VALUE id FROM ONLY $b_id) {
+				THROW "E_NOT_FOUND";
+			};
+
+			UPDATE $b_id SET
+				url = $b_url ?? url,
+				events = $b_events ?? events,
+				active = $b_active ?? active,
+				secret = $b_secret ?? secret
+			WHERE id;
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.bind(("b_url", &changes.url))
+		.bind(("b_events", &changes.events))
+		.bind(("b_active", changes.active))
+		.bind(("b_secret", &changes.secret))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error updating webhook");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "webhook.update", &id).detail(json!({
+			"url": changes.url,
+			"events": changes.events,
+			"active": changes.active,
+			"secret": changes.secret.is_some(),
+		})),
+	)
+	.await;
+
+	fetch(db, &id).await
+}
+
+/// Drops the subscription, its queued deliveries end up in the dead letters
+pub async fn delete(db: &DbAuth, actor: &Actor<'_>, id: &str) -> Result<(), Status> {
+	let id = parse_key(id, "webhooks")?;
+
+	let mut query = db
+		.query("webhooks.delete", "RETURN DELETE $b_id RETURN BEFORE;")
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error deleting webhook");
+			Status::InternalServerError
+		})?;
+
+	let deleted: Vec<Thing> = query.take((query.num_statements() - 1, "id")).map_err(|e| {
+		error!(error = %e, "Error getting webhook");
+		Status::InternalServerError
+	})?;
+
+	if deleted.is_empty() {
+		return Err(Status::NotFound);
+	}
+
+	trail::record(db, AuditEntry::new(actor, "webhook.delete", &id)).await;
+
+	Ok(())
+}
+
+pub async fn ping(db: &DbAuth, id: &str) -> Result<(), Status> {
+	let id = parse_key(id, "webhooks")?;
+	fetch(db, &id).await?;
+
+	delivery::ping(db, &id).await.map_err(|e| {
+		error!(error = %e, "Error queueing ping");
+		Status::InternalServerError
+	})
+}
+
+/// Deliveries that ran out of attempts, newest first
+pub async fn dead(db: &DbAuth, filter: DeadFilter) -> Result<Page<DeadToSend>, Status> {
+	let (page, per_page) = paging(filter.page, filter.per_page);
+	let webhook = parse_optional(filter.webhook.as_deref(), "webhooks")?;
+
+	let mut query = db
+		.read(
+			"webhooks.dead",
+			r#"
+			RETURN count(SELECT id FROM webhook_dead WHERE $b_webhook = NONE OR webhook = $b_webhook);
+			RETURN SELECT * FROM webhook_dead WHERE $b_webhook = NONE OR webhook = $b_webhook
+				ORDER BY failed_at DESC LIMIT $b_limit START $b_start;
+			"#,
+		)
+		.bind(("b_webhook", webhook))
+		.bind(("b_limit", per_page))
+		.bind(("b_start", (page - 1) * per_page))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying dead deliveries");
+			Status::InternalServerError
+		})?;
+
+	let dead: Vec<DeadRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting dead deliveries");
+		Status::InternalServerError
+	})?;
+
+	let total: Option<u64> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error counting dead deliveries");
+		Status::InternalServerError
+	})?;
+
+	Ok(Page {
+		items: dead.into_iter().map(DeadToSend::from).collect(),
+		page,
+		per_page,
+		total: total.unwrap_or(0),
+	})
+}
+
+/// Queues a dead delivery again with its attempts reset, the subscription must still exist
+pub async fn retry(db: &DbAuth, actor: &Actor<'_>, id: &str) -> Result<(), Status> {
+	let id = parse_key(id, "webhook_dead")?;
+
+	let mut query = db
+		.query(
+			"webhooks.retry",
+			r#"
+			BEGIN TRANSACTION;
+
+			LET $q_dead = (SELECT * FROM ONLY $b_id);
+			IF !$q_dead {
+				THROW "E_NOT_FOUND";
+			};
+			IF !(SELECT VALUE id FROM ONLY $q_dead.webhook) {
+				THROW "E_NOT_FOUND";
+			};
+
+			CREATE webhook_deliveries CONTENT {
+				webhook: $q_dead.webhook,
+				event: $q_dead.event,
+				body: $q_dead.body,
+				attempts: 0,
+				next_at: time::now(),
+				created_at: $q_dead.created_at,
+			} RETURN NONE;
+			DELETE $b_id;
+
+			COMMIT TRANSACTION;
+			"#,
+		)
+		.bind(("b_id", &id))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error retrying delivery");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(db, AuditEntry::new(actor, "webhook.retry", &id)).await;
+
+	Ok(())
+}
+
+#[derive(rocket::serde::Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct SecretRow {
+	id: Thing,
+	secret: Cow<'static, str>,
+}
+
+async fn fetch(db: &DbAuth, id: &Thing) -> Result<WebhookToSend, Status> {
+	let sql = format!("RETURN SELECT {WEBHOOK_FIELDS} FROM ONLY $b_id;");
+
+	let mut query = db.query("webhooks.fetch", sql).bind(("b_id", id)).await.map_err(|e| {
+		error!(error = %e, "Error querying webhook");
+		Status::InternalServerError
+	})?;
+
+	let webhook: Option<WebhookRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting webhook");
+		Status::InternalServerError
+	})?;
+
+	webhook.map(WebhookToSend::from).ok_or(Status::NotFound)
+}
+
+fn check_url(url: &str) -> Result<(), Status> {
+	match reqwest::Url::parse(url) {
+		Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+		_ => Err(Status::UnprocessableEntity),
+	}
+}
+
+fn check_events(events: &[Cow<'static, str>]) -> Result<(), Status> {
+	if events.is_empty() || events.iter().any(|event| event.trim().is_empty()) {
+		return Err(Status::UnprocessableEntity);
+	}
+
+	Ok(())
+}
@@ -0,0 +1,77 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use rocket::serde::json::Value;
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+
+use crate::app::providers::services::audit::trail::Outcome;
+
+#[derive(Debug, FromForm)]
+pub struct AuditFilter {
+	pub page: Option<u32>,
+	pub per_page: Option<u32>,
+	/// Entries done by the user or about it
+	pub user: Option<String>,
+	pub project: Option<String>,
+	/// Action or prefix of it, `auth.` gives every authentication event
+	pub action: Option<String>,
+	pub outcome: Option<String>,
+	/// RFC 3339 bounds of the time range
+	pub from: Option<String>,
+	pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuditRow {
+	pub id: Thing,
+	pub at: Datetime,
+	pub actor: Option<Thing>,
+	pub action: Cow<'static, str>,
+	pub target: Option<Thing>,
+	pub project: Option<Thing>,
+	pub outcome: Option<Outcome>,
+	pub status: Option<u16>,
+	pub ip: Option<Cow<'static, str>>,
+	pub user_agent: Option<Cow<'static, str>>,
+	pub request_id: Option<Cow<'static, str>>,
+	pub detail: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuditToSend {
+	pub id: Cow<'static, str>,
+	pub at: DateTime<Utc>,
+	pub actor: Option<Cow<'static, str>>,
+	pub action: Cow<'static, str>,
+	pub target: Option<Cow<'static, str>>,
+	pub project: Option<Cow<'static, str>>,
+	pub outcome: Outcome,
+	pub status: Option<u16>,
+	pub ip: Option<Cow<'static, str>>,
+	pub user_agent: Option<Cow<'static, str>>,
+	pub request_id: Option<Cow<'static, str>>,
+	pub detail: Value,
+}
+
+impl From<AuditRow> for AuditToSend {
+	fn from(row: AuditRow) -> Self {
+		AuditToSend {
+			id: row.id.to_string().into(),
+			at: row.at.0,
+			actor: row.actor.map(|actor| actor.to_string().into()),
+			action: row.action,
+			target: row.target.map(|target| target.to_string().into()),
+			project: row.project.map(|project| project.to_string().into()),
+			// entries written before the outcome was kept were all successful
+			outcome: row.outcome.unwrap_or(Outcome::Success),
+			status: row.status,
+			ip: row.ip,
+			user_agent: row.user_agent,
+			request_id: row.request_id,
+			detail: row.detail.unwrap_or(Value::Null),
+		}
+	}
+}
@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+use crate::app::providers::models::center::Center;
+use crate::app::providers::models::user::Role;
+
+#[derive(Debug, FromForm)]
+pub struct CenterFilter {
+	pub page: Option<u32>,
+	pub per_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CenterChanges {
+	pub name: Cow<'static, str>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CenterRow {
+	#[serde(flatten)]
+	pub center: Center,
+	pub projects: u64,
+	pub staff: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CenterToSend {
+	pub id: Cow<'static, str>,
+	pub name: Cow<'static, str>,
+	pub projects: u64,
+	pub staff: u64,
+}
+
+impl From<CenterRow> for CenterToSend {
+	fn from(row: CenterRow) -> Self {
+		CenterToSend {
+			id: row.center.id.to_string().into(),
+			name: row.center.name,
+			projects: row.projects,
+			staff: row.staff,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StaffRole {
+	pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StaffRow {
+	pub id: Thing,
+	pub username: Cow<'static, str>,
+	pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StaffToSend {
+	pub id: Cow<'static, str>,
+	pub username: Cow<'static, str>,
+	pub role: Role,
+}
+
+impl From<StaffRow> for StaffToSend {
+	fn from(staff: StaffRow) -> Self {
+		StaffToSend {
+			id: staff.id.to_string().into(),
+			username: staff.username,
+			role: staff.role,
+		}
+	}
+}
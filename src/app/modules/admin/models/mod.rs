@@ -0,0 +1,6 @@
+pub mod audit;
+pub mod centers;
+pub mod page;
+pub mod projects;
+pub mod users;
+pub mod webhooks;
@@ -0,0 +1,21 @@
+use rocket::serde::Serialize;
+
+pub const DEFAULT_PER_PAGE: u32 = 20;
+pub const MAX_PER_PAGE: u32 = 100;
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Page<T> {
+	pub items: Vec<T>,
+	pub page: u32,
+	pub per_page: u32,
+	pub total: u64,
+}
+
+/// Page number starting at 1 and page size clamped to `MAX_PER_PAGE`
+pub fn paging(page: Option<u32>, per_page: Option<u32>) -> (u32, u32) {
+	let page = page.unwrap_or(1).max(1);
+	let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+	(page, per_page)
+}
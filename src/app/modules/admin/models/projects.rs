@@ -0,0 +1,79 @@
+use std::borrow::Cow;
+
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+use crate::app::providers::models::project::{ProjectState, SignupMode};
+
+#[derive(Debug, FromForm)]
+pub struct ProjectFilter {
+	pub page: Option<u32>,
+	pub per_page: Option<u32>,
+	pub center: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewProject {
+	pub name: Cow<'static, str>,
+	pub center: Cow<'static, str>,
+	pub state: Option<ProjectState>,
+	pub signup: Option<SignupMode>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProjectChanges {
+	pub name: Option<Cow<'static, str>>,
+	pub state: Option<ProjectState>,
+	pub signup: Option<SignupMode>,
+}
+
+/// A project as listed, the secret is never part of it
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProjectRow {
+	pub id: Thing,
+	pub name: Cow<'static, str>,
+	pub state: Cow<'static, str>,
+	#[serde(default)]
+	pub signup: SignupMode,
+	pub center: Option<Thing>,
+	pub members: u64,
+	pub pending: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProjectToSend {
+	pub id: Cow<'static, str>,
+	pub name: Cow<'static, str>,
+	pub state: Cow<'static, str>,
+	pub signup: SignupMode,
+	pub center: Option<Cow<'static, str>>,
+	pub members: u64,
+	pub pending: u64,
+}
+
+impl From<ProjectRow> for ProjectToSend {
+	fn from(project: ProjectRow) -> Self {
+		ProjectToSend {
+			id: project.id.to_string().into(),
+			name: project.name,
+			state: project.state,
+			signup: project.signup,
+			center: project.center.map(|c| c.to_string().into()),
+			members: project.members,
+			pending: project.pending,
+		}
+	}
+}
+
+/// Only returned once, when the project is created
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProjectCreated {
+	#[serde(flatten)]
+	pub project: ProjectToSend,
+	pub token: Cow<'static, str>,
+}
@@ -0,0 +1,127 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+
+use crate::app::providers::models::center::CenterRole;
+use crate::app::providers::models::user::{Role, UserState};
+
+#[derive(Debug, FromForm)]
+pub struct UserFilter {
+	pub page: Option<u32>,
+	pub per_page: Option<u32>,
+	pub q: Option<String>,
+	pub project: Option<String>,
+	pub center: Option<String>,
+	pub role: Option<String>,
+	pub state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UserRow {
+	pub id: Thing,
+	pub username: Cow<'static, str>,
+	pub project: Option<Thing>,
+	pub state: Option<Cow<'static, str>>,
+	pub disabled: Option<bool>,
+	pub password_reset: Option<bool>,
+	pub roles: Vec<CenterRole>,
+	pub projects: Vec<Thing>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UserToSend {
+	pub id: Cow<'static, str>,
+	pub username: Cow<'static, str>,
+	pub project: Option<Cow<'static, str>>,
+	pub state: Cow<'static, str>,
+	pub disabled: bool,
+	pub password_reset: bool,
+	pub roles: Vec<RoleToSend>,
+	pub projects: Vec<Cow<'static, str>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RoleToSend {
+	pub center: Cow<'static, str>,
+	pub name: Cow<'static, str>,
+	pub role: Role,
+}
+
+impl From<UserRow> for UserToSend {
+	fn from(user: UserRow) -> Self {
+		UserToSend {
+			id: user.id.to_string().into(),
+			username: user.username,
+			project: user.project.map(|p| p.to_string().into()),
+			state: user.state.unwrap_or("active".into()),
+			disabled: user.disabled.unwrap_or(false),
+			password_reset: user.password_reset.unwrap_or(false),
+			roles: user
+				.roles
+				.into_iter()
+				.map(|r| RoleToSend {
+					center: r.id.to_string().into(),
+					name: r.name,
+					role: r.role,
+				})
+				.collect(),
+			projects: user.projects.into_iter().map(|p| p.to_string().into()).collect(),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UserProject {
+	pub project: Cow<'static, str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PasswordReset {
+	pub password: Cow<'static, str>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UserStateChange {
+	pub state: UserState,
+	pub reason: Option<Cow<'static, str>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StateChangeRow {
+	pub previous: UserState,
+	pub state: UserState,
+	pub reason: Option<Cow<'static, str>>,
+	pub by: Option<Thing>,
+	pub at: Datetime,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct StateChangeToSend {
+	pub previous: UserState,
+	pub state: UserState,
+	pub reason: Option<Cow<'static, str>>,
+	pub by: Option<Cow<'static, str>>,
+	pub at: DateTime<Utc>,
+}
+
+impl From<StateChangeRow> for StateChangeToSend {
+	fn from(row: StateChangeRow) -> Self {
+		StateChangeToSend {
+			previous: row.previous,
+			state: row.state,
+			reason: row.reason,
+			by: row.by.map(|user| user.to_string().into()),
+			at: row.at.0,
+		}
+	}
+}
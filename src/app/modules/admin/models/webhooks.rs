@@ -0,0 +1,121 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewWebhook {
+	pub url: Cow<'static, str>,
+	/// Audit actions to receive, as `auth.signup` or `member.approve`, `*` for all of them
+	pub events: Vec<Cow<'static, str>>,
+	/// Generated when missing
+	pub secret: Option<Cow<'static, str>>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookChanges {
+	pub url: Option<Cow<'static, str>>,
+	pub events: Option<Vec<Cow<'static, str>>>,
+	pub active: Option<bool>,
+	pub secret: Option<Cow<'static, str>>,
+}
+
+/// A subscription as listed, the secret is never part of it
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookRow {
+	pub id: Thing,
+	pub url: Cow<'static, str>,
+	pub events: Vec<Cow<'static, str>>,
+	pub active: bool,
+	pub created_at: Datetime,
+	pub queued: u64,
+	pub dead: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookToSend {
+	pub id: Cow<'static, str>,
+	pub url: Cow<'static, str>,
+	pub events: Vec<Cow<'static, str>>,
+	pub active: bool,
+	pub created_at: DateTime<Utc>,
+	/// Deliveries waiting to be sent or retried
+	pub queued: u64,
+	pub dead: u64,
+}
+
+impl From<WebhookRow> for WebhookToSend {
+	fn from(row: WebhookRow) -> Self {
+		WebhookToSend {
+			id: row.id.to_string().into(),
+			url: row.url,
+			events: row.events,
+			active: row.active,
+			created_at: row.created_at.0,
+			queued: row.queued,
+			dead: row.dead,
+		}
+	}
+}
+
+/// Only returned once, when the subscription is created
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookCreated {
+	#[serde(flatten)]
+	pub webhook: WebhookToSend,
+	pub secret: Cow<'static, str>,
+}
+
+#[derive(Debug, FromForm)]
+pub struct DeadFilter {
+	pub page: Option<u32>,
+	pub per_page: Option<u32>,
+	pub webhook: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeadRow {
+	pub id: Thing,
+	pub webhook: Thing,
+	pub event: Cow<'static, str>,
+	pub body: Cow<'static, str>,
+	pub attempts: u32,
+	pub error: Cow<'static, str>,
+	pub created_at: Datetime,
+	pub failed_at: Datetime,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeadToSend {
+	pub id: Cow<'static, str>,
+	pub webhook: Cow<'static, str>,
+	pub event: Cow<'static, str>,
+	pub body: Cow<'static, str>,
+	pub attempts: u32,
+	pub error: Cow<'static, str>,
+	pub created_at: DateTime<Utc>,
+	pub failed_at: DateTime<Utc>,
+}
+
+impl From<DeadRow> for DeadToSend {
+	fn from(row: DeadRow) -> Self {
+		DeadToSend {
+			id: row.id.to_string().into(),
+			webhook: row.webhook.to_string().into(),
+			event: row.event,
+			body: row.body,
+			attempts: row.attempts,
+			error: row.error,
+			created_at: row.created_at.0,
+			failed_at: row.failed_at.0,
+		}
+	}
+}
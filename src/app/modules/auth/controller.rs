@@ -1,52 +1,74 @@
 use rocket::http::Status;
-use rocket::serde::json::Json;
+use rocket::serde::json::{json, Json};
 use rocket::State;
 
 use super::handlers::{
-    global,
-    // interv
+	authorize as authz,
+	events,
+	global,
+	// interv
 };
 
 use super::models::auth::{
-    // AuthToken,
-    AuthUser
+	// AuthToken,
+	AuthUser,
+};
+
+use super::models::authorize::{
+	AuthorizeBatchRequest, AuthorizeRequest, Decision, ResourceDecision,
 };
 
 use super::models::credentials::{
 	// CredentialsJoin,
-    CredentialsLogin,
-    // CredentialsRefresh,
-    CredentialsSignup,
+	CredentialsCenter,
+	CredentialsLogin,
+	CredentialsPassword,
+	// CredentialsRefresh,
+	CredentialsSignup,
 };
 
-use crate::app::providers::services::auth::claims::Claims;
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::services::audit::request::RequestMeta;
+use crate::app::providers::services::audit::trail::AuditEntry;
+use crate::app::providers::services::auth::claims::{Claims, ResetClaims};
 use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::metrics::registry::metrics;
 // use crate::app::providers::services::auth::token::Token;
 
 pub fn routes() -> Vec<rocket::Route> {
-	routes![options_all, signup, login, g_refresh,] // join, refresh, ]
-}
-
-#[options("/<_..>")]
-pub async fn options_all() -> Status {
-	Status::Ok
+	routes![signup, login, g_refresh, center, logout, password, authorize, authorize_batch,]
+	// join, refresh, ]
 }
 
 #[post("/signup", data = "<credentials>")]
 async fn signup(
 	db: &State<DbAuth>,
+	settings: &State<Settings>,
+	meta: RequestMeta,
 	credentials: Json<CredentialsSignup>,
 ) -> Result<Json<AuthUser>, Status> {
 	let cred = credentials.into_inner();
-
-	let response = global::signup(db, cred).await?;
-
-	Ok(Json(response))
+	let invitation = match cred.code.as_deref() {
+		Some(code) => global::invitation_of(db, code).await,
+		None => None,
+	};
+	let entry = AuditEntry::event("auth.signup", &meta).detail(json!({
+		"username": cred.username,
+		"invitation": invitation.map(|id| id.to_string()),
+		"project": cred.project,
+	}));
+
+	let response = global::signup(db, settings, cred).await;
+	events::authenticated(db, entry, &response).await;
+
+	Ok(Json(response?))
 }
 
 #[post("/login", data = "<credentials>")]
 async fn login(
 	db: &State<DbAuth>,
+	settings: &State<Settings>,
+	meta: RequestMeta,
 	credentials: Json<CredentialsLogin>,
 ) -> Result<Json<AuthUser>, Status> {
 	// let mut cred = credentials.into_inner();
@@ -60,16 +82,118 @@ async fn login(
 	// 	cred.password = temp.password;
 	// }
 
-	let response = global::login(db, cred).await?;
+	let entry =
+		AuditEntry::event("auth.login", &meta).detail(json!({ "username": cred.username }));
+
+	let response = global::login(db, settings, cred).await;
+	metrics().login(&response);
+	events::authenticated(db, entry, &response).await;
 
-	Ok(Json(response))
+	Ok(Json(response?))
 }
 
 #[get("/refresh")]
-async fn g_refresh(db: &State<DbAuth>, claims: Claims) -> Result<Json<AuthUser>, Status> {
-	let user = global::get_auth_from_id(db, &claims.id).await?;
+async fn g_refresh(
+	db: &State<DbAuth>,
+	settings: &State<Settings>,
+	meta: RequestMeta,
+	claims: Claims,
+) -> Result<Json<AuthUser>, Status> {
+	let entry = AuditEntry::event("auth.refresh", &meta).actor(claims.id.parse().ok());
+
+	let sid = claims.sid.as_deref().unwrap_or_default();
+	let center = claims.center.as_deref();
+	let user = global::get_auth_from_id(db, settings, &claims.id, sid, center).await;
+	events::authenticated(db, entry, &user).await;
+
+	Ok(Json(user?))
+}
+
+#[post("/center", data = "<credentials>")]
+async fn center(
+	db: &State<DbAuth>,
+	settings: &State<Settings>,
+	meta: RequestMeta,
+	claims: Claims,
+	credentials: Json<CredentialsCenter>,
+) -> Result<Json<AuthUser>, Status> {
+	let cred = credentials.into_inner();
+	let entry = AuditEntry::event("auth.center", &meta)
+		.actor(claims.id.parse().ok())
+		.detail(json!({ "center": cred.center }));
+
+	let sid = claims.sid.as_deref().unwrap_or_default();
+	let user = global::select_center(db, settings, &claims.id, sid, &cred.center).await;
+	events::authenticated(db, entry, &user).await;
+
+	Ok(Json(user?))
+}
+
+#[post("/logout")]
+async fn logout(
+	db: &State<DbAuth>,
+	meta: RequestMeta,
+	claims: Claims,
+) -> Result<Status, Status> {
+	let entry = AuditEntry::event("auth.logout", &meta).actor(claims.id.parse().ok());
+
+	let sid = claims.sid.as_deref().unwrap_or_default();
+	let result = global::logout(db, sid).await;
+	events::done(db, entry, &result).await;
+
+	result.map(|_| Status::NoContent)
+}
+
+#[post("/password", data = "<credentials>")]
+async fn password(
+	db: &State<DbAuth>,
+	meta: RequestMeta,
+	claims: ResetClaims,
+	credentials: Json<CredentialsPassword>,
+) -> Result<Status, Status> {
+	let ResetClaims(claims) = claims;
+	let cred = credentials.into_inner();
+	let entry = AuditEntry::event("auth.password", &meta).actor(claims.id.parse().ok());
 
-	Ok(Json(user))
+	let result = global::change_password(db, &claims.id, cred).await;
+	events::done(db, entry, &result).await;
+
+	result.map(|_| Status::NoContent)
+}
+
+#[post("/authorize", data = "<request>")]
+async fn authorize(
+	db: &State<DbAuth>,
+	settings: &State<Settings>,
+	request: Json<AuthorizeRequest>,
+) -> Result<Json<Decision>, Status> {
+	let request = request.into_inner();
+
+	let decision =
+		authz::authorize(db, settings, &request.token, &request.action, request.resource)
+			.await?;
+
+	Ok(Json(decision))
+}
+
+#[post("/authorize/batch", data = "<request>")]
+async fn authorize_batch(
+	db: &State<DbAuth>,
+	settings: &State<Settings>,
+	request: Json<AuthorizeBatchRequest>,
+) -> Result<Json<Vec<ResourceDecision>>, Status> {
+	let request = request.into_inner();
+
+	let decisions = authz::authorize_batch(
+		db,
+		settings,
+		&request.token,
+		&request.action,
+		request.resources,
+	)
+	.await?;
+
+	Ok(Json(decisions))
 }
 
 // {{{
@@ -0,0 +1,262 @@
+use std::borrow::Cow;
+
+use rocket::http::Status;
+use rocket::serde::Deserialize;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::modules::auth::models::authorize::{
+	Decision, ResourceDecision, ResourceKind, ResourceRef,
+};
+
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::models::record::parse_record;
+use crate::app::providers::models::user::Role;
+
+use crate::app::providers::services::auth::claims::Claims;
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::auth::session;
+use crate::app::providers::services::auth::token::Token;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct StaffRole {
+	id: Thing,
+	role: Role,
+}
+
+/// Projects and centers a resource is attached to
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Membership {
+	id: Thing,
+	#[serde(default)]
+	centers: Vec<Thing>,
+}
+
+struct Subject {
+	id: Thing,
+	roles: Vec<StaffRole>,
+	projects: Vec<Thing>,
+}
+
+pub async fn authorize(
+	db: &DbAuth,
+	settings: &Settings,
+	token: &str,
+	action: &str,
+	resource: ResourceRef,
+) -> Result<Decision, Status> {
+	let mut decisions = authorize_batch(db, settings, token, action, vec![resource]).await?;
+
+	Ok(decisions.remove(0).decision)
+}
+
+pub async fn authorize_batch(
+	db: &DbAuth,
+	settings: &Settings,
+	token: &str,
+	action: &str,
+	resources: Vec<ResourceRef>,
+) -> Result<Vec<ResourceDecision>, Status> {
+	let claims = match decode_subject(db, settings, token).await? {
+		Some(claims) => claims,
+		None => return Ok(deny_all(resources, "invalid subject token")),
+	};
+
+	if !claims.perms.iter().any(|perm| perm == action) {
+		return Ok(deny_all(resources, format!("missing permission {}", action)));
+	}
+
+	let subject_id: Thing = match claims.id.parse() {
+		Ok(id) => id,
+		Err(_) => return Ok(deny_all(resources, "invalid subject token")),
+	};
+
+	let things: Vec<Option<Thing>> = resources.iter().map(parse_resource).collect();
+	let ids_of = |kind: ResourceKind| -> Vec<Thing> {
+		resources
+			.iter()
+			.zip(things.iter())
+			.filter(|(resource, _)| resource.kind == kind)
+			.filter_map(|(_, thing)| thing.clone())
+			.collect()
+	};
+
+	let mut query = db
+		.query(
+			"auth.authorize",
+			r#"
+			RETURN SELECT out AS id, role FROM roled WHERE in = $b_subject;
+			RETURN SELECT VALUE out FROM join WHERE in = $b_subject AND pending != true;
+			RETURN SELECT id, ->join[WHERE pending != true].out->belongs->centers AS centers FROM $b_users;
+			RETURN SELECT id, ->belongs->centers AS centers FROM $b_projects;
+			RETURN SELECT id FROM $b_centers;
+			"#,
+		)
+		.bind(("b_subject", &subject_id))
+		.bind(("b_users", ids_of(ResourceKind::User)))
+		.bind(("b_projects", ids_of(ResourceKind::Project)))
+		.bind(("b_centers", ids_of(ResourceKind::Center)))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying authorization facts");
+			Status::InternalServerError
+		})?;
+
+	let centers: Vec<Membership> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting centers");
+		Status::InternalServerError
+	})?;
+
+	let projects: Vec<Membership> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting projects");
+		Status::InternalServerError
+	})?;
+
+	let users: Vec<Membership> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting users");
+		Status::InternalServerError
+	})?;
+
+	let joined: Vec<Thing> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting memberships");
+		Status::InternalServerError
+	})?;
+
+	let roles: Vec<StaffRole> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting roles");
+		Status::InternalServerError
+	})?;
+
+	let subject = Subject {
+		id: subject_id,
+		roles,
+		projects: joined,
+	};
+
+	let decisions = resources
+		.into_iter()
+		.zip(things)
+		.map(|(resource, thing)| {
+			let decision = match thing {
+				None => Decision::deny("invalid resource id"),
+				Some(thing) => {
+					let known = match resource.kind {
+						ResourceKind::User => &users,
+						ResourceKind::Project => &projects,
+						ResourceKind::Center => &centers,
+					};
+
+					match known.iter().find(|m| m.id == thing) {
+						None => Decision::deny("resource not found"),
+						Some(membership) => decide(&subject, resource.kind, membership),
+					}
+				}
+			};
+
+			ResourceDecision {
+				resource,
+				decision,
+			}
+		})
+		.collect();
+
+	Ok(decisions)
+}
+
+fn decide(subject: &Subject, kind: ResourceKind, resource: &Membership) -> Decision {
+	if subject.roles.iter().any(|r| r.role == Role::Admin) {
+		return Decision::allow("admin");
+	}
+
+	match kind {
+		ResourceKind::Center => match subject.roles.iter().find(|r| r.id == resource.id) {
+			Some(staff) => Decision::allow(format!("{} in the center", staff.role.as_str())),
+			None => Decision::deny("no role in the center"),
+		},
+		ResourceKind::Project => {
+			if subject.projects.contains(&resource.id) {
+				return Decision::allow("member of the project");
+			}
+
+			match staff_in(subject, &resource.centers) {
+				Some(role) => {
+					Decision::allow(format!("{} in the project center", role.as_str()))
+				}
+				None => Decision::deny("not a member of the project"),
+			}
+		}
+		ResourceKind::User => {
+			if subject.id == resource.id {
+				return Decision::allow("own user");
+			}
+
+			match staff_in(subject, &resource.centers) {
+				Some(role) => {
+					Decision::allow(format!("{} in a center of the user", role.as_str()))
+				}
+				None => Decision::deny("no relation to the user"),
+			}
+		}
+	}
+}
+
+/// Highest staff role (therapist or above) the subject holds in any of the centers
+fn staff_in(subject: &Subject, centers: &[Thing]) -> Option<Role> {
+	subject
+		.roles
+		.iter()
+		.filter(|r| centers.contains(&r.id) && r.role.is_at_least(Role::Thera))
+		.map(|r| r.role)
+		.reduce(|highest, role| {
+			if role.is_at_least(highest) {
+				role
+			} else {
+				highest
+			}
+		})
+}
+
+fn parse_resource(resource: &ResourceRef) -> Option<Thing> {
+	parse_record(&resource.id, resource.kind.table()).ok()
+}
+
+fn deny_all(
+	resources: Vec<ResourceRef>,
+	reason: impl Into<Cow<'static, str>>,
+) -> Vec<ResourceDecision> {
+	let reason = reason.into();
+
+	resources
+		.into_iter()
+		.map(|resource| ResourceDecision {
+			resource,
+			decision: Decision::deny(reason.clone()),
+		})
+		.collect()
+}
+
+/// Claims of a global token, only while its session is open. Project tokens are refused,
+/// every project service holds the secret they are signed with and could sign one for anybody
+async fn decode_subject(
+	db: &DbAuth,
+	settings: &Settings,
+	token: &str,
+) -> Result<Option<Claims>, Status> {
+	let token = Token(token.to_string().into());
+	let claims = match token.decode(settings.secret_key.as_bytes()) {
+		Ok(data) => data.claims,
+		Err(_) => return Ok(None),
+	};
+
+	let sid = claims.sid.as_deref().unwrap_or_default();
+	match session::is_live(db, sid, &claims.id).await {
+		Ok(true) => Ok(Some(claims)),
+		Ok(false) => Ok(None),
+		Err(e) => {
+			error!(error = %e, "Error checking session");
+			Err(Status::InternalServerError)
+		}
+	}
+}
@@ -0,0 +1,29 @@
+use rocket::http::Status;
+
+use crate::app::modules::auth::models::auth::AuthUser;
+
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::db::DbAuth;
+
+/// Writes the outcome of an action that hands out tokens, on success the entry is
+/// completed with the user and its project
+pub async fn authenticated(db: &DbAuth, entry: AuditEntry, result: &Result<AuthUser, Status>) {
+	let entry = match result {
+		Ok(user) => entry
+			.actor(user.id.parse().ok())
+			.project(user.project.as_str().and_then(|p| p.parse().ok())),
+		Err(status) => entry.failed(*status),
+	};
+
+	trail::record(db, entry).await;
+}
+
+/// Writes the outcome of any other action
+pub async fn done<T>(db: &DbAuth, entry: AuditEntry, result: &Result<T, Status>) {
+	let entry = match result {
+		Ok(_) => entry,
+		Err(status) => entry.failed(*status),
+	};
+
+	trail::record(db, entry).await;
+}
@@ -3,119 +3,209 @@ use std::borrow::Cow;
 use rocket::http::Status;
 use rocket::serde::json::{self, Value};
 use surrealdb::sql::Thing;
+use surrealdb::Response;
+use tracing::{debug, error, warn};
 
-use crate::app::modules::auth::models::auth::{AuthUser, ProjectToSend};
-use crate::app::modules::auth::models::credentials::{CredentialsLogin, CredentialsSignup};
+use crate::app::modules::auth::models::auth::{AuthUser, CenterToSend, ProjectToSend};
+use crate::app::modules::auth::models::credentials::{
+	CredentialsLogin, CredentialsPassword, CredentialsSignup,
+};
 
-use crate::app::providers::config::getter::ConfigGetter;
+use crate::app::providers::config::settings::Settings;
 
+use crate::app::providers::models::center::{CenterRole, CenterRolePrev};
+use crate::app::providers::models::permission::RolePerms;
 use crate::app::providers::models::project::Project;
-use crate::app::providers::models::user::{Role, UserGlobal, UserGlobalPrev};
+use crate::app::providers::models::record::parse_optional;
+use crate::app::providers::models::user::{
+	Role, UnknownRole, UserGlobal, UserGlobalPrev, UserState,
+};
 
 use crate::app::providers::services::auth::claims::Claims;
 use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::auth::perms;
+use crate::app::providers::services::auth::session;
+use crate::app::providers::services::metrics::registry::metrics;
 // use crate::app::providers::services::auth::token::Token;
 
-pub async fn signup(db: &DbAuth, cred: CredentialsSignup) -> Result<AuthUser, Status> {
-	let project: Option<Thing> = match cred.project {
-		Some(project) => {
-			let temp: Vec<&str> = project.split(":").collect();
-			if temp.len() != 2
-				|| temp[0].is_empty()
-				|| temp[1].is_empty()
-				|| temp[0] != "projects"
-			{
-				eprintln!("Bad project id");
-				return Err(Status::BadRequest);
-			}
-
-			Some(Thing::from((temp[0], temp[1]))) // doesn't work well with numbers
-		}
-		None => None,
+/// Joins through an invitation code, or straight to the project when its signup mode allows it.
+/// It all runs in one transaction, a failure leaves neither the user nor any of its edges behind
+pub async fn signup(
+	db: &DbAuth,
+	settings: &Settings,
+	cred: CredentialsSignup,
+) -> Result<AuthUser, Status> {
+	let head = match (&cred.code, &cred.project) {
+		(Some(_), None) => SIGNUP_WITH_CODE,
+		(None, Some(_)) => SIGNUP_WITH_PROJECT,
+		_ => return Err(Status::BadRequest),
 	};
 
-	// create user
-	let mut query =
-        db.0.query(r#"
-            LET $q_user = CREATE users CONTENT { username: $b_username, password: $b_password, project: $b_project };
-            LET $q_user_id = $q_user.id;
-
-            RELATE $q_user_id->roled->(SELECT VALUE (->belongs->centers)[0] FROM ONLY $b_project) SET role = 'parti';
-            RELATE $q_user_id->join->$b_project;
-
-            -- RETURN CREATE users CONTENT { username: $b_username, password: $b_password, project: $b_project };
-            -- RETURN SELECT VALUE center.name FROM ONLY $b_project LIMIT 1;
-
-            RETURN SELECT * FROM $q_user_id;
-            RETURN SELECT * FROM ONLY $b_project LIMIT 1;
-            RETURN $b_project.center.name;
-        "#)
-        .bind(("b_username", &cred.username))
-        .bind(("b_password", &cred.password))
-        .bind(("b_project", project))
-        .await
-        .map_err(|_| {
-            dbg!("Error creating user");
-            Status::InternalServerError
-        })?;
-
-	let center: Option<Cow<'static, str>> =
-		query.take(query.num_statements() - 1).map_err(|_| {
-			dbg!("Error getting center");
-			Status::InternalServerError
-		})?;
-
-	let project: Option<Project> = query.take(query.num_statements() - 1).map_err(|_| {
-		dbg!("Error getting project");
-		Status::InternalServerError
-	})?;
+	let project = parse_optional(cred.project.as_deref(), "projects")?;
+
+	let sql = format!(
+		r#"
+		BEGIN TRANSACTION;
+		{head}
+		IF !$q_project {{
+			THROW "project not found";
+		}};
+		IF $q_project.state != 'active' {{
+			THROW "project not active";
+		}};
+
+		LET $q_center = (SELECT VALUE (->belongs->centers)[0] FROM ONLY $q_project.id);
+		IF !$q_center {{
+			THROW "project has no center";
+		}};
+
+		LET $q_user = (CREATE ONLY users CONTENT {{
+			username: $b_username,
+			password: $b_password,
+			project: IF $q_pending {{ NONE }} ELSE {{ $q_project.id }},
+		}});
+
+		IF !$q_pending {{
+			RELATE ($q_user.id)->roled->$q_center SET role = $q_role;
+		}};
+		RELATE ($q_user.id)->join->($q_project.id) SET pending = $q_pending,
+			requested_at = time::now(), role = $q_role, invitation = $q_invitation.id;
+
+		COMMIT TRANSACTION;
+
+		LET $q_joined = (SELECT * FROM ONLY $q_user.project LIMIT 1);
+
+		RETURN SELECT *, {PENDING} FROM ONLY $q_user.id;
+		RETURN $q_joined;
+		RETURN $q_joined.center.name;
+		RETURN SELECT out AS id, out.name AS name, role FROM roled WHERE in = $q_user.id;
+		RETURN SELECT role, project, perms FROM role_perms WHERE project = NONE OR project = $q_joined.id;
+		{NEW_SESSION}
+		"#
+	);
 
-	let user: UserGlobal = query
-		.take(query.num_statements() - 1)
-		.map(|user: Option<UserGlobalPrev>| {
-			let user = user.expect("User not found");
-
-			UserGlobal {
-				id: user.id,
-				project: user.project,
-				username: user.username,
-				password: user.password,
-				// role: user.role.into(),
-				web_token: user.web_token,
-			}
-		})
+	let mut query = db
+		.query("auth.signup", sql)
+		.bind(("b_username", &cred.username))
+		.bind(("b_password", &cred.password))
+		.bind(("b_code", &cred.code))
+		.bind(("b_project", project))
+		.await
 		.map_err(|e| {
-			let foo = surrealdb::Error::from(e).to_string();
-			if foo.contains("users_username") {
-				return Status::Conflict;
-			} // index unique
-
-			eprintln!("Error getting user: {:?}", foo);
-			dbg!("Error getting user");
+			error!(error = %e, "Error creating user");
 			Status::InternalServerError
 		})?;
 
-	// let mut user = AuthUser::from(&user);
-    let mut user = AuthUser {
-        id: user.id.to_string().into(),
-        role: Some("parti".into()),
-        project: project.as_ref().map(|p| p.id.to_string().into()).unwrap_or(Value::Null),
-        username: user.username,
-        g_token: "".into(),
-        p_token: None,
-    };
+	let errors: Vec<String> =
+		query.take_errors().into_values().map(|e| e.to_string()).collect();
+	if errors.iter().any(|e| e.contains("users_username")) {
+		return Err(Status::Conflict); // index unique
+	}
+	if errors
+		.iter()
+		.any(|e| e.contains("invalid invitation") || e.contains("signup not allowed"))
+	{
+		return Err(Status::Forbidden);
+	}
+	if errors.iter().any(|e| e.contains("project not found")) {
+		return Err(Status::NotFound);
+	}
+	if errors.iter().any(|e| e.contains("project not active")) {
+		return Err(Status::UnprocessableEntity);
+	}
+	if !errors.is_empty() {
+		error!(?errors, "Error creating user");
+		return Err(Status::InternalServerError);
+	}
 
-	add_tokens(&mut user, project, center)?;
+	auth_user_from_response(settings, query, None)
+}
 
-	Ok(user)
+/// Invitation behind a code, for the audit trail which must not keep the code itself
+pub async fn invitation_of(db: &DbAuth, code: &str) -> Option<Thing> {
+	let mut query = match db
+		.query(
+			"auth.invitation",
+			"RETURN SELECT VALUE id FROM ONLY invitations WHERE code = $b_code LIMIT 1;",
+		)
+		.bind(("b_code", code))
+		.await
+	{
+		Ok(query) => query,
+		Err(e) => {
+			error!(error = %e, "Error querying invitation");
+			return None;
+		}
+	};
+
+	query.take(query.num_statements() - 1).unwrap_or_else(|e| {
+		error!(error = %e, "Error getting invitation");
+		None
+	})
 }
 
+/// Project of the user's membership request still waiting for approval
+const PENDING: &str = "(->join[WHERE pending = true].out)[0] AS pending";
+
+/// Opens the session the tokens are issued for, users on standby get none
+const NEW_SESSION: &str = r#"
+	RETURN IF $q_user AND ($q_user.state ?? 'active') != 'standby' {
+		(CREATE ONLY sessions CONTENT {
+			user: $q_user.id,
+			created_at: time::now(),
+			refreshed_at: time::now(),
+			revoked: false,
+		}).id
+	};
+"#;
+
+/// Keeps the session of the token being refreshed, as long as it is still open
+const KEEP_SESSION: &str = r#"
+	RETURN (UPDATE $b_sid SET refreshed_at = time::now()
+		WHERE user = $q_user.id AND revoked = false)[0].id;
+"#;
+
+/// Consumes one use of the invitation, which brings the role, in any project not closed
+const SIGNUP_WITH_CODE: &str = r#"
+		LET $q_invitation = (SELECT * FROM ONLY invitations WHERE code = $b_code LIMIT 1);
+		IF !$q_invitation
+			OR $q_invitation.revoked
+			OR $q_invitation.uses >= $q_invitation.max_uses
+			OR $q_invitation.expires_at <= time::now() {
+			THROW "invalid invitation";
+		};
+
+		LET $q_project = (SELECT * FROM ONLY $q_invitation.project);
+		IF $q_project.signup = 'closed' {
+			THROW "signup not allowed";
+		};
+
+		UPDATE $q_invitation.id SET uses += 1;
+
+		LET $q_role = $q_invitation.role;
+		LET $q_pending = false;
+"#;
+
+/// Open projects take participants as they come, approval ones queue them
+const SIGNUP_WITH_PROJECT: &str = r#"
+		LET $q_project = (SELECT * FROM ONLY $b_project);
+		IF $q_project AND $q_project.signup NOTINSIDE ['open', 'approval'] {
+			THROW "signup not allowed";
+		};
+
+		LET $q_invitation = NONE;
+		LET $q_role = 'parti';
+		LET $q_pending = $q_project.signup = 'approval';
+"#;
+
 pub fn add_tokens(
+	settings: &Settings,
 	user: &mut AuthUser,
 	project: Option<Project>,
 	center: Option<Cow<'static, str>>,
+	sid: &str,
 ) -> Result<(), Status> {
-	user.g_token = generate_global_token(&user.id, user.role.as_ref())?;
+	user.g_token = generate_global_token(settings, user, sid)?;
 
 	if let Some(project) = project {
 		let project_name = project.name.clone();
@@ -127,13 +217,17 @@ pub fn add_tokens(
 			user.project["center"] = json::to_value(center).unwrap();
 		}
 
-		user.p_token = generate_project_token(
-			center.unwrap(),
-			project_name,
-			project_secret,
-			&user.id,
-			user.role.as_ref(),
-		)?;
+		// the project token only makes sense inside the center the user is acting for
+		if let Some(center) = center {
+			user.p_token = generate_project_token(
+				settings,
+				center,
+				project_name,
+				project_secret,
+				user,
+				sid,
+			)?;
+		}
 	}
 
 	Ok(())
@@ -141,7 +235,7 @@ pub fn add_tokens(
 
 // pub async fn generate_guest_user(db: &DbAuth) -> Result<UserGlobal, Status> {
 // 	let mut query =
-// 		db.0.query(r#"
+// 		db.query(r#"
 //         LET $q_password = rand::string();
 
 //         RETURN CREATE users CONTENT { username: rand::string(), password: $q_password, role: 'guest' };
@@ -180,8 +274,13 @@ pub fn add_tokens(
 // 	Ok(user)
 // }
 
-pub async fn login(db: &DbAuth, cred: CredentialsLogin) -> Result<AuthUser, Status> {
-	let user_to_send = get_user_from_username(db, &cred.username, &cred.password).await?;
+pub async fn login(
+	db: &DbAuth,
+	settings: &Settings,
+	cred: CredentialsLogin,
+) -> Result<AuthUser, Status> {
+	let user_to_send =
+		get_user_from_username(db, settings, &cred.username, &cred.password).await?;
 
 	// user_to_send.g_token = generate_global_token(&user_to_send.id, role)?;
 	// user_to_send.p_token = generate_global_token(&user_to_send.id, Role::Parti)?;
@@ -210,33 +309,34 @@ pub async fn login(db: &DbAuth, cred: CredentialsLogin) -> Result<AuthUser, Stat
 // }
 
 fn generate_project_token(
+	settings: &Settings,
 	ns: Cow<'static, str>,
 	db: Cow<'static, str>,
 	project_secret: Cow<'static, str>,
-	user_id: &Cow<'static, str>,
-	role: Option<&Cow<'static, str>>,
+	user: &AuthUser,
+	sid: &str,
 ) -> Result<Option<Cow<'static, str>>, Status> {
-	let mut claims = Claims::new(
-		ns,
-		db,
-		"user".into(),
-		"user_scope".into(),
-		user_id.to_string().into(),
-		role.cloned(),
-	);
-
-	match claims.encode_for_access(project_secret.as_bytes()) {
-		Ok(token) => Ok(Some(token.into())),
-		Err(_) => {
-			dbg!("Error encoding token");
+	let mut claims =
+		Claims::new(ns, db, "user".into(), "user_scope".into(), user.id.clone(), user.role);
+	claims.perms = user.perms.clone();
+	claims.sid = Some(sid.to_string().into());
+
+	match claims.encode_for_access(project_secret.as_bytes(), settings.token_ttl) {
+		Ok(token) => {
+			metrics().token("project");
+			Ok(Some(token.into()))
+		}
+		Err(e) => {
+			error!(error = %e, "Error encoding token");
 			Err(Status::InternalServerError)
 		}
 	}
 }
 
 fn generate_global_token(
-	user_id: &Cow<'static, str>,
-	role: Option<&Cow<'static, str>>,
+	settings: &Settings,
+	user: &AuthUser,
+	sid: &str,
 ) -> Result<Cow<'static, str>, Status> {
 	// check if user is admin
 
@@ -245,173 +345,312 @@ fn generate_global_token(
 		"main".into(),
 		"user".into(),
 		"user_scope".into(), // admin_scope
-		user_id.to_string().into(),
-		role.cloned(),
+		user.id.clone(),
+		user.role,
 	);
-
-	let secret_key = ConfigGetter::get_secret_key();
-	match claims.encode_for_access(secret_key.as_bytes()) {
-		Ok(token) => Ok(token.into()),
-		Err(_) => {
-			dbg!("Error encoding token");
+	claims.center = user.center.clone();
+	claims.perms = user.perms.clone();
+	claims.sid = Some(sid.to_string().into());
+	claims.password_reset = user.password_reset;
+
+	match claims.encode_for_access(settings.secret_key.as_bytes(), settings.token_ttl) {
+		Ok(token) => {
+			metrics().token("global");
+			Ok(token.into())
+		}
+		Err(e) => {
+			error!(error = %e, "Error encoding token");
 			Err(Status::InternalServerError)
 		}
 	}
 }
 
-pub async fn get_auth_from_id(db: &DbAuth, id: &Cow<'static, str>) -> Result<AuthUser, Status> {
-	let mut query =
-		db.0.query(
+/// Changes the password after checking the current one, clearing any forced reset
+pub async fn change_password(
+	db: &DbAuth,
+	id: &str,
+	cred: CredentialsPassword,
+) -> Result<(), Status> {
+	let mut query = db
+		.query(
+			"auth.change_password",
 			r#"
-            LET $q_user = (SELECT * FROM ONLY users WHERE id = <record> $b_id LIMIT 1);
-            LET $q_project = (SELECT * FROM ONLY $q_user.project LIMIT 1);
-            LET $q_center = (SELECT * FROM ONLY $q_project.center LIMIT 1);
-            
-            RETURN $q_user;
-            RETURN $q_project;
-            RETURN $q_center.name;
-            RETURN (SELECT VALUE ->roled[where out is $q_center.id].role AS role FROM ONLY $q_user.id)[0];
-            "#,
+			LET $q_user = (SELECT VALUE id FROM ONLY users WHERE id = <record> $b_id AND crypto::argon2::compare(password, $b_password) LIMIT 1);
+
+			RETURN IF $q_user THEN (UPDATE $q_user SET password = $b_new_password, password_reset = false RETURN NONE) END;
+			RETURN $q_user;
+			"#,
 		)
 		.bind(("b_id", id))
+		.bind(("b_password", &cred.password))
+		.bind(("b_new_password", &cred.new_password))
 		.await
-		.map_err(|_| {
-			dbg!("Error querying user");
-			Status::InternalServerError
-		})?;
-
-	let role: Option<Cow<'static, str>> =
-		query.take(query.num_statements() - 1).map_err(|_| {
-			dbg!("Error getting center");
-			Status::InternalServerError
-		})?;
-
-	let center: Option<Cow<'static, str>> =
-		query.take(query.num_statements() - 1).map_err(|_| {
-			dbg!("Error getting center");
+		.map_err(|e| {
+			error!(error = %e, "Error changing password");
 			Status::InternalServerError
 		})?;
 
-	let project: Option<Project> = query.take(query.num_statements() - 1).map_err(|_| {
-		dbg!("Error getting project");
+	let user: Option<Thing> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting user");
 		Status::InternalServerError
 	})?;
 
-	let user: UserGlobal = query
-		.take(query.num_statements() - 1)
-		.map(|user: Option<UserGlobalPrev>| {
-			let user = user.unwrap();
-
-			UserGlobal {
-				id: user.id,
-				project: user.project,
-				username: user.username,
-				password: user.password,
-				// role: user.role.into(),
-				web_token: user.web_token,
-			}
-		})
-		.map_err(|_| {
-			dbg!("Error getting user");
-			Status::InternalServerError
-		})?;
+	user.ok_or(Status::Unauthorized)?;
 
-	// let mut auth_user = AuthUser::from(&user);
-    let mut auth_user = AuthUser {
-        id: user.id.to_string().into(),
-        role,
-        project: project.as_ref().map(|p| p.id.to_string().into()).unwrap_or(Value::Null),
-        username: user.username,
-        g_token: "".into(),
-        p_token: None,
-    };
+	Ok(())
+}
 
-	add_tokens(&mut auth_user, project, center)?;
+/// Closes the session of the token, every token issued for it stops working
+pub async fn logout(db: &DbAuth, sid: &str) -> Result<(), Status> {
+	match session::revoke(db, sid).await {
+		Ok(true) => Ok(()),
+		Ok(false) => Err(Status::Unauthorized),
+		Err(e) => {
+			error!(error = %e, "Error closing session");
+			Err(Status::InternalServerError)
+		}
+	}
+}
 
-	Ok(auth_user)
+/// Issues the tokens again acting for `center`, which must be one of the user's centers and,
+/// for a user inside a project, the center of that project
+pub async fn select_center(
+	db: &DbAuth,
+	settings: &Settings,
+	id: &str,
+	sid: &str,
+	center: &str,
+) -> Result<AuthUser, Status> {
+	let user = get_auth_from_id(db, settings, id, sid, Some(center)).await?;
+
+	if user.center.as_deref() != Some(center) {
+		debug!(user = id, center, "User has no role in the requested center");
+		return Err(Status::Forbidden);
+	}
+
+	Ok(user)
+}
+
+/// Issues the tokens again within the session `sid`
+pub async fn get_auth_from_id(
+	db: &DbAuth,
+	settings: &Settings,
+	id: &str,
+	sid: &str,
+	center: Option<&str>,
+) -> Result<AuthUser, Status> {
+	let sid: Thing = sid.parse().map_err(|_| Status::Unauthorized)?;
+
+	let sql = format!(
+		r#"
+            LET $q_user = (SELECT *, {PENDING} FROM ONLY users WHERE id = <record> $b_id AND disabled != true LIMIT 1);
+            LET $q_project = (SELECT * FROM ONLY $q_user.project LIMIT 1);
+
+            RETURN $q_user;
+            RETURN $q_project;
+            RETURN $q_project.center.name;
+            RETURN SELECT out AS id, out.name AS name, role FROM roled WHERE in = $q_user.id;
+            RETURN SELECT role, project, perms FROM role_perms WHERE project = NONE OR project = $q_user.project;
+            {KEEP_SESSION}
+            "#
+	);
+
+	let query =
+		db.query("auth.refresh", sql).bind(("b_id", id)).bind(("b_sid", &sid)).await.map_err(
+			|e| {
+				error!(error = %e, "Error querying user");
+				Status::InternalServerError
+			},
+		)?;
+
+	auth_user_from_response(settings, query, center)
 }
 
 pub async fn get_user_from_username(
 	db: &DbAuth,
-	username: &Cow<'static, str>,
-	password: &Cow<'static, str>,
+	settings: &Settings,
+	username: &str,
+	password: &str,
 ) -> Result<AuthUser, Status> {
-	let mut query =
-		db.0.query(
-			r#"
-            LET $q_user = (SELECT * FROM ONLY users WHERE username = $b_username AND crypto::argon2::compare(password, $b_password) LIMIT 1);
+	let sql = format!(
+		r#"
+            LET $q_user = (SELECT *, {PENDING} FROM ONLY users WHERE username = $b_username AND crypto::argon2::compare(password, $b_password) AND disabled != true LIMIT 1);
             LET $q_project = (SELECT * FROM ONLY $q_user.project LIMIT 1);
-            LET $q_center = (SELECT * FROM ONLY $q_project.center LIMIT 1);
 
             RETURN $q_user;
             RETURN $q_project;
-            RETURN $q_center.name;
-            RETURN (SELECT VALUE ->roled[WHERE out IS $q_center.id].role AS role FROM ONLY $q_user.id)[0];
-            "#,
-		)
+            RETURN $q_project.center.name;
+            RETURN SELECT out AS id, out.name AS name, role FROM roled WHERE in = $q_user.id;
+            RETURN SELECT role, project, perms FROM role_perms WHERE project = NONE OR project = $q_user.project;
+            {NEW_SESSION}
+            "#
+	);
+
+	let query = db
+		.query("auth.login", sql)
 		.bind(("b_username", username))
-        .bind(("b_password", password))
+		.bind(("b_password", password))
 		.await
-		.map_err(|_| {
-			dbg!("Error querying user");
+		.map_err(|e| {
+			error!(error = %e, "Error querying user");
 			Status::InternalServerError
 		})?;
 
-	let role: Option<Cow<'static, str>> =
-		query.take(query.num_statements() - 1).map_err(|_| {
-			dbg!("Error getting center");
-			Status::InternalServerError
-		})?;
+	auth_user_from_response(settings, query, None)
+}
+
+/// Builds the user to send from the last six statements of a lookup: user, project, project
+/// center name, every (center, role) pair, the role permission sets and the session, acting
+/// for `center` when the user has a role in it. The state of the user decides what it gets
+fn auth_user_from_response(
+	settings: &Settings,
+	mut query: Response,
+	center: Option<&str>,
+) -> Result<AuthUser, Status> {
+	let sid: Option<Thing> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting session");
+		Status::InternalServerError
+	})?;
 
-	let center: Option<Cow<'static, str>> =
-		query.take(query.num_statements() - 1).map_err(|_| {
-			dbg!("Error getting center");
+	let role_perms: Vec<RolePerms> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting permissions");
+		Status::InternalServerError
+	})?;
+
+	let centers: Vec<CenterRolePrev> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting roles");
+		Status::InternalServerError
+	})?;
+
+	// a role this version does not know gives nothing rather than failing the whole login
+	let centers: Vec<CenterRole> = centers
+		.into_iter()
+		.filter_map(|center| {
+			let id = center.id.to_string();
+			center
+				.try_into()
+				.map_err(|e: UnknownRole| warn!(center = id, error = %e, "Skipping role"))
+				.ok()
+		})
+		.collect();
+
+	let project_center: Option<Cow<'static, str>> =
+		query.take(query.num_statements() - 1).map_err(|e| {
+			error!(error = %e, "Error getting center");
 			Status::InternalServerError
 		})?;
 
-	let project: Option<Project> = query.take(query.num_statements() - 1).map_err(|_| {
-		dbg!("Error getting project");
+	let project: Option<Project> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting project");
 		Status::InternalServerError
 	})?;
 
 	let user: UserGlobal = query
-		.take(query.num_statements() - 1)
-		.map(|user: Option<UserGlobalPrev>| {
-			let user = user.unwrap();
-
-			UserGlobal {
-				id: user.id,
-				project: user.project,
-				username: user.username,
-				password: user.password,
-				// role: user.role.into(),
-				web_token: user.web_token,
-			}
-		})
-		.map_err(|_| {
-			dbg!("Error getting user");
+		.take::<Option<UserGlobalPrev>>(query.num_statements() - 1)
+		.map_err(|e| {
+			error!(error = %e, "Error getting user");
 			Status::InternalServerError
-		})?;
+		})?
+		.map(|user: UserGlobalPrev| UserGlobal {
+			id: user.id,
+			project: user.project,
+			username: user.username,
+			password: user.password,
+			// role: user.role.into(),
+			web_token: user.web_token,
+			password_reset: user.password_reset,
+			pending: user.pending,
+			state: user.state,
+		})
+		.ok_or(Status::Unauthorized)?;
+
+	let state = user.state.unwrap_or_default();
+	if state == UserState::Standby {
+		return Err(Status::Locked);
+	}
+
+	let sid = sid.ok_or(Status::Unauthorized)?;
 
-	// let mut auth_user = AuthUser::from(&user);
-    let mut auth_user = AuthUser {
-        id: user.id.to_string().into(),
-        role,
-        project: project.as_ref().map(|p| p.id.to_string().into()).unwrap_or(Value::Null),
-        username: user.username,
-        g_token: "".into(),
-        p_token: None,
-    };
+	let active = active_center(&centers, center, project.as_ref());
 
-	add_tokens(&mut auth_user, project, center)?;
+	// the project token is bound to the center of the project, acting for another center
+	// would leave the user without it
+	let project_center = match (active, project.as_ref()) {
+		(Some(active), Some(project))
+			if active.id != project.center && state != UserState::Exited =>
+		{
+			if center.is_some() {
+				debug!(user = %user.id, center = %active.id, "Center is not the project's one");
+				return Err(Status::Conflict);
+			}
+
+			warn!(user = %user.id, project = %project.id, "User has no role in the project center");
+			None
+		}
+		_ => project_center,
+	};
+
+	let role = active.map(|c| c.role);
+
+	let mut perms = role.map(|role| effective_perms(&role_perms, role)).unwrap_or_default();
+	if state == UserState::Completed {
+		perms.retain(|perm| perm.ends_with(":read"));
+	}
+
+	// exited users keep their account but no longer act inside the project
+	let project_center = match state {
+		UserState::Exited => None,
+		_ => project_center,
+	};
+
+	// after a reset the tokens only serve to change the password
+	let password_reset = user.password_reset.unwrap_or(false);
+	let project_center = if password_reset {
+		perms.clear();
+		None
+	} else {
+		project_center
+	};
 
-	// if let Some(project) = project {
-	// 	auth_user.project = json::to_value(ProjectToSend::from(project)).unwrap();
-	// }
+	let mut auth_user = AuthUser {
+		id: user.id.to_string().into(),
+		role,
+		center: active.map(|c| c.id.to_string().into()),
+		centers: centers.iter().map(CenterToSend::from).collect(),
+		perms,
+		project: project.as_ref().map(|p| p.id.to_string().into()).unwrap_or(Value::Null),
+		username: user.username,
+		password_reset,
+		pending: user.pending.map(|p| p.to_string().into()),
+		state,
+		g_token: "".into(),
+		p_token: None,
+	};
 
-	// if let Some(center) = center {
-	// 	auth_user.project["center"] = json::to_value(center).unwrap();
-	// }
+	add_tokens(settings, &mut auth_user, project, project_center, &sid.to_string())?;
 
 	Ok(auth_user)
 }
+
+/// Requested center if the user has a role there, otherwise the center of the user's project,
+/// otherwise the first center found
+fn active_center<'a>(
+	centers: &'a [CenterRole],
+	requested: Option<&str>,
+	project: Option<&Project>,
+) -> Option<&'a CenterRole> {
+	requested
+		.and_then(|requested| centers.iter().find(|c| c.id.to_string() == requested))
+		.or_else(|| project.and_then(|p| centers.iter().find(|c| c.id == p.center)))
+		.or_else(|| centers.first())
+}
+
+/// Permission set configured for the project, otherwise the global one, otherwise the defaults
+fn effective_perms(role_perms: &[RolePerms], role: Role) -> Vec<Cow<'static, str>> {
+	role_perms
+		.iter()
+		.filter(|r| r.role == role)
+		.max_by_key(|r| r.project.is_some())
+		.map(|r| r.perms.clone())
+		.unwrap_or_else(|| perms::defaults(role).iter().map(|p| Cow::Borrowed(*p)).collect())
+}
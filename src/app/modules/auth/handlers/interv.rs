@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 
 use rocket::http::Status;
+use tracing::error;
 
 use crate::app::modules::auth::models::credentials::CredentialsJoin;
 
@@ -26,23 +27,23 @@ pub async fn inject_guest(
 		cred.ns, cred.db,
 	);
 
-	let mut query = db.0.query(sql).await.map_err(|_| {
-		dbg!("Error querying");
+	let mut query = db.0.query(sql).await.map_err(|e| {
+		error!(error = %e, "Error querying");
 		Status::InternalServerError
 	})?;
 
 	let pass: Option<String> = query
 		.take(query.num_statements() - 1)
 		.map(|pass: Option<String>| pass)
-		.map_err(|_| {
-			dbg!("Error getting pass");
+		.map_err(|e| {
+			error!(error = %e, "Error getting pass");
 			Status::InternalServerError
 		})?;
 
 	match pass {
 		Some(pass) => Ok(pass.into()),
 		None => {
-			dbg!("There was an error injecting guest user");
+			error!("There was an error injecting guest user");
 			Err(Status::InternalServerError)
 		}
 	}
@@ -58,7 +59,7 @@ pub async fn join(
 
 	let user_id = user.id.to_string().into();
 	if user_id != claims.id {
-		dbg!("User id does not match token id");
+		error!("User id does not match token id");
 		return Err(Status::InternalServerError);
 	}
 
@@ -67,8 +68,8 @@ pub async fn join(
 
 	match claims.encode_for_access(secret_key.as_ref()) {
 		Ok(token) => Ok(token.into()),
-		Err(_) => {
-			dbg!("Error encoding token");
+		Err(e) => {
+			error!(error = %e, "Error encoding token");
 			Err(Status::InternalServerError)
 		}
 	}
@@ -83,16 +84,16 @@ pub async fn refresh_interv_token(
 
 	let mut claims = match token.decode(secret_key.as_ref()) {
 		Ok(claims) => claims.claims,
-		Err(_) => {
-			dbg!("Error decoding token");
+		Err(e) => {
+			error!(error = %e, "Error decoding token");
 			return Err(Status::InternalServerError);
 		}
 	};
 
 	match claims.encode_for_access(secret_key.as_ref()) {
 		Ok(token) => Ok(token.into()),
-		Err(_) => {
-			dbg!("Error encoding token");
+		Err(e) => {
+			error!(error = %e, "Error encoding token");
 			Err(Status::InternalServerError)
 		}
 	}
@@ -108,27 +109,23 @@ async fn validate_pass(db: &DbAuth, cred: &CredentialsJoin) -> Result<UserInterv
 		cred.ns, cred.db, cred.pass,
 	);
 
-	let mut query = db.0.query(sql).await.map_err(|_| {
-		dbg!("Error querying");
+	let mut query = db.0.query(sql).await.map_err(|e| {
+		error!(error = %e, "Error querying");
 		Status::InternalServerError
 	})?;
 
-	let user: UserInterv = query
-		.take(query.num_statements() - 1)
-		.map(|user: Option<UserIntervPrev>| {
-			let user = user.unwrap();
-
-			UserInterv {
-				id: user.id,
-				// pass: user.pass,
-				role: user.role.into(),
-				state: user.state.into(),
-			}
-		})
-		.map_err(|_| {
-			dbg!("Error getting user");
+	let user: UserIntervPrev = query
+		.take::<Option<UserIntervPrev>>(query.num_statements() - 1)
+		.map_err(|e| {
+			error!(error = %e, "Error getting user");
 			Status::InternalServerError
-		})?;
+		})?
+		.ok_or(Status::Unauthorized)?;
+
+	let user = UserInterv::try_from(user).map_err(|e| {
+		error!(error = %e, "Error getting user");
+		Status::Forbidden
+	})?;
 
 	Ok(user)
 }
@@ -142,21 +139,21 @@ async fn get_project_token(
 		.query("RETURN SELECT VALUE token FROM ONLY projects WHERE name = $b_project LIMIT 1;")
 		.bind(("b_project", project_name))
 		.await
-		.map_err(|_| {
-			dbg!("Error querying");
+		.map_err(|e| {
+			error!(error = %e, "Error querying");
 			Status::InternalServerError
 		})?;
 
 	let project_token: Option<String> =
-		query.take(query.num_statements() - 1).map_err(|_| {
-			dbg!("Error getting token");
+		query.take(query.num_statements() - 1).map_err(|e| {
+			error!(error = %e, "Error getting token");
 			Status::InternalServerError
 		})?;
 
 	match project_token {
 		Some(secret_key) => Ok(secret_key),
 		None => {
-			dbg!("Error getting global token");
+			error!("Error getting global token");
 			Err(Status::InternalServerError)
 		}
 	}
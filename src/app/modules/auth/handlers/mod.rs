@@ -1,2 +1,4 @@
+pub mod authorize;
+pub mod events;
 pub mod global;
 // pub mod interv;
@@ -3,16 +3,25 @@ use std::borrow::Cow;
 use rocket::serde::json::Value;
 use rocket::serde::Serialize;
 
+use crate::app::providers::models::center::CenterRole;
 use crate::app::providers::models::project::Project;
+use crate::app::providers::models::user::{Role, UserState};
 // use crate::app::providers::models::user::UserGlobal;
 
 #[derive(Debug, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct AuthUser {
 	pub id: Cow<'static, str>,
-	pub role: Option<Cow<'static, str>>,
+	pub role: Option<Role>,
+	pub center: Option<Cow<'static, str>>,
+	pub centers: Vec<CenterToSend>,
+	pub perms: Vec<Cow<'static, str>>,
 	pub project: Value,
 	pub username: Cow<'static, str>,
+	pub password_reset: bool,
+	/// Project waiting for a coordinator to approve the membership
+	pub pending: Option<Cow<'static, str>>,
+	pub state: UserState,
 	pub g_token: Cow<'static, str>,
 	pub p_token: Option<Cow<'static, str>>,
 }
@@ -48,6 +57,26 @@ impl From<Project> for ProjectToSend {
 	}
 }
 
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CenterToSend {
+	pub id: Cow<'static, str>,
+	pub name: Cow<'static, str>,
+	pub role: Role,
+}
+
+impl From<&CenterRole> for CenterToSend {
+	fn from(center: &CenterRole) -> Self {
+		CenterToSend {
+			id: center.id.to_string().into(),
+			name: center.name.clone(),
+			role: center.role,
+		}
+	}
+}
+
+/// Only answered by the intervention routes, unmounted for now
+#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct AuthToken {
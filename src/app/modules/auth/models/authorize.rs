@@ -0,0 +1,75 @@
+use std::borrow::Cow;
+
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthorizeRequest {
+	pub token: Cow<'static, str>,
+	pub action: Cow<'static, str>,
+	pub resource: ResourceRef,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthorizeBatchRequest {
+	pub token: Cow<'static, str>,
+	pub action: Cow<'static, str>,
+	pub resources: Vec<ResourceRef>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResourceRef {
+	pub kind: ResourceKind,
+	pub id: Cow<'static, str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum ResourceKind {
+	User,
+	Project,
+	Center,
+}
+
+impl ResourceKind {
+	pub fn table(&self) -> &'static str {
+		match self {
+			ResourceKind::User => "users",
+			ResourceKind::Project => "projects",
+			ResourceKind::Center => "centers",
+		}
+	}
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Decision {
+	pub allow: bool,
+	pub reason: Cow<'static, str>,
+}
+
+impl Decision {
+	pub fn allow(reason: impl Into<Cow<'static, str>>) -> Self {
+		Decision {
+			allow: true,
+			reason: reason.into(),
+		}
+	}
+
+	pub fn deny(reason: impl Into<Cow<'static, str>>) -> Self {
+		Decision {
+			allow: false,
+			reason: reason.into(),
+		}
+	}
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResourceDecision {
+	pub resource: ResourceRef,
+	#[serde(flatten)]
+	pub decision: Decision,
+}
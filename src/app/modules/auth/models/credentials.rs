@@ -7,6 +7,9 @@ use serde::Deserialize;
 pub struct CredentialsSignup {
 	pub username: Cow<'static, str>,
 	pub password: Cow<'static, str>,
+	/// Invitation code, it decides the project and the role
+	pub code: Option<Cow<'static, str>>,
+	/// Project to join without a code, only for open and approval projects
 	pub project: Option<Cow<'static, str>>,
 }
 
@@ -17,6 +20,21 @@ pub struct CredentialsLogin {
 	pub password: Cow<'static, str>,
 }
 
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CredentialsPassword {
+	pub password: Cow<'static, str>,
+	pub new_password: Cow<'static, str>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CredentialsCenter {
+	pub center: Cow<'static, str>,
+}
+
+/// Only read by the intervention routes, unmounted for now
+#[allow(dead_code)]
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CredentialsJoin {
@@ -25,6 +43,8 @@ pub struct CredentialsJoin {
 	pub pass: Cow<'static, str>,
 }
 
+/// Only read by the intervention routes, unmounted for now
+#[allow(dead_code)]
 #[derive(Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CredentialsRefresh {
@@ -0,0 +1,47 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+
+use super::handlers::invitation;
+
+use super::models::invitation::{InvitationFilter, InvitationToSend, NewInvitation};
+
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::auth::roles::{AtLeast, Coord};
+
+pub fn routes() -> Vec<rocket::Route> {
+	routes![create, list, revoke]
+}
+
+#[post("/", data = "<new>")]
+async fn create(
+	db: &State<DbAuth>,
+	coord: AtLeast<Coord>,
+	new: Json<NewInvitation>,
+) -> Result<Json<InvitationToSend>, Status> {
+	let invitation = invitation::create(db, &coord.actor(), new.into_inner()).await?;
+
+	Ok(Json(invitation))
+}
+
+#[get("/?<filter..>")]
+async fn list(
+	db: &State<DbAuth>,
+	coord: AtLeast<Coord>,
+	filter: InvitationFilter,
+) -> Result<Json<Vec<InvitationToSend>>, Status> {
+	let invitations = invitation::list(db, &coord.claims, filter).await?;
+
+	Ok(Json(invitations))
+}
+
+#[delete("/<code>")]
+async fn revoke(
+	db: &State<DbAuth>,
+	coord: AtLeast<Coord>,
+	code: &str,
+) -> Result<Status, Status> {
+	invitation::revoke(db, &coord.actor(), code).await?;
+
+	Ok(Status::NoContent)
+}
@@ -0,0 +1,192 @@
+use rocket::http::Status;
+use rocket::serde::json::json;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::modules::invitations::models::invitation::{
+	InvitationFilter, InvitationRow, InvitationToSend, NewInvitation,
+};
+
+use crate::app::providers::models::record::{parse_optional, parse_record};
+use crate::app::providers::models::user::Role;
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::access::{
+	check_errors, is_admin, user_id, CHECK_COORD,
+};
+use crate::app::providers::services::auth::claims::Claims;
+use crate::app::providers::services::auth::db::DbAuth;
+
+const DEFAULT_EXPIRES_IN: u32 = 72;
+const MAX_EXPIRES_IN: u32 = 24 * 30;
+
+/// Issues a code for the project, only admins and the coordinators of its center can
+pub async fn create(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	invitation: NewInvitation,
+) -> Result<InvitationToSend, Status> {
+	if !matches!(invitation.role, Role::Parti | Role::Thera | Role::Coord) {
+		return Err(Status::UnprocessableEntity);
+	}
+
+	let max_uses = invitation.max_uses.unwrap_or(1);
+	let expires_in = invitation.expires_in.unwrap_or(DEFAULT_EXPIRES_IN);
+	if max_uses == 0 || expires_in == 0 || expires_in > MAX_EXPIRES_IN {
+		return Err(Status::UnprocessableEntity);
+	}
+
+	let project = parse_record(&invitation.project, "projects")?;
+
+	let sql = format!(
+		r#"
+		BEGIN TRANSACTION;
+
+		LET $q_project = (SELECT id, center FROM ONLY $b_project);
+		IF !$q_project {{
+			THROW "E_NOT_FOUND";
+		}};
+		LET $q_center = $q_project.center;
+		{CHECK_COORD}
+
+		LET $q_invitation = (CREATE ONLY invitations CONTENT {{
+			code: string::uppercase(rand::string(10)),
+			project: $b_project,
+			role: $b_role,
+			uses: 0,
+			max_uses: $b_max_uses,
+			expires_at: time::now() + <duration> $b_expires_in,
+			revoked: false,
+			created_by: $b_user,
+		}});
+
+		COMMIT TRANSACTION;
+
+		RETURN $q_invitation;
+		"#
+	);
+
+	let mut query = db
+		.query("invitations.create", sql)
+		.bind(("b_project", &project))
+		.bind(("b_user", user_id(actor.claims)))
+		.bind(("b_admin", is_admin(actor.claims)))
+		.bind(("b_role", invitation.role))
+		.bind(("b_max_uses", max_uses))
+		.bind(("b_expires_in", format!("{expires_in}h")))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error creating invitation");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let invitation: Option<InvitationRow> =
+		query.take(query.num_statements() - 1).map_err(|e| {
+			error!(error = %e, "Error getting invitation");
+			Status::InternalServerError
+		})?;
+
+	let invitation = invitation.ok_or(Status::InternalServerError)?;
+
+	trail::record(
+		db,
+		AuditEntry::new(actor, "invitation.create", &invitation.id)
+			.project(Some(project))
+			.detail(json!({
+				"role": invitation.role,
+				"max_uses": max_uses,
+			})),
+	)
+	.await;
+
+	Ok(invitation.into())
+}
+
+/// Codes the caller could have issued, optionally narrowed to a project or to the usable ones
+pub async fn list(
+	db: &DbAuth,
+	claims: &Claims,
+	filter: InvitationFilter,
+) -> Result<Vec<InvitationToSend>, Status> {
+	let project = parse_optional(filter.project.as_deref(), "projects")?;
+
+	let mut query = db
+		.read(
+			"invitations.list",
+			r#"
+			RETURN SELECT * FROM invitations
+				WHERE ($b_project = NONE OR project = $b_project)
+				AND ($b_active = NONE
+					OR $b_active = (!revoked AND uses < max_uses AND expires_at > time::now()))
+				AND ($b_admin OR project.center INSIDE
+					(SELECT VALUE out FROM roled WHERE in = $b_user AND role = 'coord'))
+				ORDER BY expires_at DESC;
+			"#,
+		)
+		.bind(("b_project", project))
+		.bind(("b_active", filter.active))
+		.bind(("b_user", user_id(claims)))
+		.bind(("b_admin", is_admin(claims)))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying invitations");
+			Status::InternalServerError
+		})?;
+
+	let invitations: Vec<InvitationRow> =
+		query.take(query.num_statements() - 1).map_err(|e| {
+			error!(error = %e, "Error getting invitations");
+			Status::InternalServerError
+		})?;
+
+	Ok(invitations.into_iter().map(InvitationToSend::from).collect())
+}
+
+/// Revoked codes are kept, only signup stops accepting them
+pub async fn revoke(db: &DbAuth, actor: &Actor<'_>, code: &str) -> Result<(), Status> {
+	let sql = format!(
+		r#"
+		BEGIN TRANSACTION;
+
+		LET $q_invitation = (SELECT id, project.center AS center FROM ONLY invitations
+			WHERE code = $b_code LIMIT 1);
+		IF !$q_invitation {{
+			THROW "E_NOT_FOUND";
+		}};
+		LET $q_center = $q_invitation.center;
+		{CHECK_COORD}
+
+		UPDATE $q_invitation.id SET revoked = true;
+
+		COMMIT TRANSACTION;
+
+		RETURN $q_invitation.id;
+		"#
+	);
+
+	let mut query = db
+		.query("invitations.revoke", sql)
+		.bind(("b_code", code))
+		.bind(("b_user", user_id(actor.claims)))
+		.bind(("b_admin", is_admin(actor.claims)))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error revoking invitation");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let id: Option<Thing> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting invitation");
+		Status::InternalServerError
+	})?;
+
+	if let Some(id) = id {
+		trail::record(db, AuditEntry::new(actor, "invitation.revoke", &id)).await;
+	}
+
+	Ok(())
+}
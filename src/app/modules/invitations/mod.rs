@@ -0,0 +1,3 @@
+pub mod controller;
+mod handlers;
+mod models;
@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+
+use crate::app::providers::models::user::Role;
+
+#[derive(Debug, FromForm)]
+pub struct InvitationFilter {
+	pub project: Option<String>,
+	pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewInvitation {
+	pub project: Cow<'static, str>,
+	pub role: Role,
+	/// How many signups the code allows, one by default
+	pub max_uses: Option<u32>,
+	/// Hours until the code expires, three days by default
+	pub expires_in: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InvitationRow {
+	pub id: Thing,
+	pub code: Cow<'static, str>,
+	pub project: Thing,
+	pub role: Role,
+	pub uses: u32,
+	pub max_uses: u32,
+	pub expires_at: Datetime,
+	pub revoked: bool,
+	pub created_by: Option<Thing>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct InvitationToSend {
+	pub code: Cow<'static, str>,
+	pub project: Cow<'static, str>,
+	pub role: Role,
+	pub uses: u32,
+	pub max_uses: u32,
+	pub expires_at: DateTime<Utc>,
+	pub revoked: bool,
+	pub created_by: Option<Cow<'static, str>>,
+}
+
+impl From<InvitationRow> for InvitationToSend {
+	fn from(row: InvitationRow) -> Self {
+		InvitationToSend {
+			code: row.code,
+			project: row.project.to_string().into(),
+			role: row.role,
+			uses: row.uses,
+			max_uses: row.max_uses,
+			expires_at: row.expires_at.0,
+			revoked: row.revoked,
+			created_by: row.created_by.map(|user| user.to_string().into()),
+		}
+	}
+}
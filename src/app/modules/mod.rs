@@ -1,2 +1,5 @@
+pub mod admin;
 pub mod auth;
+pub mod invitations;
+pub mod projects;
 pub mod routing;
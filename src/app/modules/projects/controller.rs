@@ -0,0 +1,50 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+
+use super::handlers::pending;
+
+use super::models::pending::PendingToSend;
+
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::auth::perms::{RequirePerm, UsersManage};
+use crate::app::providers::services::auth::roles::{AtLeast, Coord};
+
+pub fn routes() -> Vec<rocket::Route> {
+	routes![list_pending, approve, reject]
+}
+
+#[get("/<id>/pending")]
+async fn list_pending(
+	db: &State<DbAuth>,
+	coord: AtLeast<Coord>,
+	id: &str,
+) -> Result<Json<Vec<PendingToSend>>, Status> {
+	let pending = pending::list(db, &coord.claims, id).await?;
+
+	Ok(Json(pending))
+}
+
+#[post("/<id>/pending/<user>/approve")]
+async fn approve(
+	db: &State<DbAuth>,
+	coord: RequirePerm<UsersManage>,
+	id: &str,
+	user: &str,
+) -> Result<Status, Status> {
+	pending::approve(db, &coord.actor(), id, user).await?;
+
+	Ok(Status::NoContent)
+}
+
+#[post("/<id>/pending/<user>/reject")]
+async fn reject(
+	db: &State<DbAuth>,
+	coord: RequirePerm<UsersManage>,
+	id: &str,
+	user: &str,
+) -> Result<Status, Status> {
+	pending::reject(db, &coord.actor(), id, user).await?;
+
+	Ok(Status::NoContent)
+}
@@ -0,0 +1,147 @@
+use rocket::http::Status;
+use tracing::error;
+
+use crate::app::modules::projects::models::pending::{PendingRow, PendingToSend};
+
+use crate::app::providers::models::record::parse_key;
+use crate::app::providers::services::audit::request::Actor;
+use crate::app::providers::services::audit::trail::{self, AuditEntry};
+use crate::app::providers::services::auth::access::{
+	check_errors, is_admin, user_id, CHECK_COORD,
+};
+use crate::app::providers::services::auth::claims::Claims;
+use crate::app::providers::services::auth::db::DbAuth;
+
+/// The project asked about, its center is the one `CHECK_COORD` looks at
+const CHECK_PROJECT: &str = r#"
+	LET $q_project = (SELECT id, center FROM ONLY $b_project);
+	IF !$q_project {
+		THROW "E_NOT_FOUND";
+	};
+	LET $q_center = $q_project.center;
+"#;
+
+/// Users waiting to join the project, oldest request first
+pub async fn list(
+	db: &DbAuth,
+	claims: &Claims,
+	id: &str,
+) -> Result<Vec<PendingToSend>, Status> {
+	let sql = format!(
+		r#"
+		{CHECK_PROJECT}
+		{CHECK_COORD}
+		RETURN SELECT in AS id, in.username AS username, requested_at FROM join
+			WHERE out = $b_project AND pending = true ORDER BY requested_at;
+		"#
+	);
+
+	let mut query = db
+		.read("pending.list", sql)
+		.bind(("b_project", parse_key(id, "projects")?))
+		.bind(("b_user", user_id(claims)))
+		.bind(("b_admin", is_admin(claims)))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error querying pending members");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	let pending: Vec<PendingRow> = query.take(query.num_statements() - 1).map_err(|e| {
+		error!(error = %e, "Error getting pending members");
+		Status::InternalServerError
+	})?;
+
+	Ok(pending.into_iter().map(PendingToSend::from).collect())
+}
+
+/// Makes the user a participant of the project and of its center
+pub async fn approve(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	user: &str,
+) -> Result<(), Status> {
+	let sql = format!(
+		r#"
+		BEGIN TRANSACTION;
+		{CHECK_PROJECT}
+		{CHECK_COORD}
+		LET $q_join = (SELECT id FROM ONLY join
+			WHERE in = $b_member AND out = $b_project AND pending = true LIMIT 1);
+		IF !$q_join {{
+			THROW "E_NOT_FOUND";
+		}};
+
+		UPDATE $q_join.id SET pending = false;
+		UPDATE $b_member SET project = $b_project;
+
+		IF count(SELECT id FROM roled WHERE in = $b_member AND out = $q_project.center) = 0 {{
+			RELATE $b_member->roled->($q_project.center) SET role = 'parti';
+		}};
+
+		COMMIT TRANSACTION;
+		"#
+	);
+
+	decide(db, actor, id, user, sql, "member.approve").await
+}
+
+/// Drops the request, the account stays without project
+pub async fn reject(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	user: &str,
+) -> Result<(), Status> {
+	let sql = format!(
+		r#"
+		BEGIN TRANSACTION;
+		{CHECK_PROJECT}
+		{CHECK_COORD}
+		LET $q_join = (SELECT id FROM ONLY join
+			WHERE in = $b_member AND out = $b_project AND pending = true LIMIT 1);
+		IF !$q_join {{
+			THROW "E_NOT_FOUND";
+		}};
+
+		DELETE $q_join.id;
+
+		COMMIT TRANSACTION;
+		"#
+	);
+
+	decide(db, actor, id, user, sql, "member.reject").await
+}
+
+async fn decide(
+	db: &DbAuth,
+	actor: &Actor<'_>,
+	id: &str,
+	user: &str,
+	sql: String,
+	action: &'static str,
+) -> Result<(), Status> {
+	let project = parse_key(id, "projects")?;
+	let member = parse_key(user, "users")?;
+
+	let mut query = db
+		.query("pending.decide", sql)
+		.bind(("b_project", &project))
+		.bind(("b_member", &member))
+		.bind(("b_user", user_id(actor.claims)))
+		.bind(("b_admin", is_admin(actor.claims)))
+		.await
+		.map_err(|e| {
+			error!(error = %e, "Error deciding membership");
+			Status::InternalServerError
+		})?;
+
+	check_errors(&mut query)?;
+
+	trail::record(db, AuditEntry::new(actor, action, &member).project(Some(project))).await;
+
+	Ok(())
+}
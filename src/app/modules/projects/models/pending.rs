@@ -0,0 +1,32 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+
+/// A `join` edge still waiting for a coordinator
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PendingRow {
+	pub id: Thing,
+	pub username: Cow<'static, str>,
+	pub requested_at: Option<Datetime>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PendingToSend {
+	pub id: Cow<'static, str>,
+	pub username: Cow<'static, str>,
+	pub requested_at: Option<DateTime<Utc>>,
+}
+
+impl From<PendingRow> for PendingToSend {
+	fn from(row: PendingRow) -> Self {
+		PendingToSend {
+			id: row.id.to_string().into(),
+			username: row.username,
+			requested_at: row.requested_at.map(|at| at.0),
+		}
+	}
+}
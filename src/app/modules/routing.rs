@@ -1,9 +1,15 @@
+use crate::app::modules::admin::controller::routes as admin_routes;
 use crate::app::modules::auth::controller::routes as auth_routes;
+use crate::app::modules::invitations::controller::routes as invitations_routes;
+use crate::app::modules::projects::controller::routes as projects_routes;
 
 pub fn router() -> rocket::fairing::AdHoc {
 	#[allow(unused_mut)]
 	rocket::fairing::AdHoc::on_ignite("Modules Routes", |mut rocket| async {
 		rocket = rocket.mount("/auth", auth_routes());
+		rocket = rocket.mount("/admin", admin_routes());
+		rocket = rocket.mount("/invitations", invitations_routes());
+		rocket = rocket.mount("/projects", projects_routes());
 
 		rocket
 	})
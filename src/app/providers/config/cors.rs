@@ -1,42 +1,91 @@
-use rocket::fairing::{Fairing, Info, Kind};
-use rocket::http::Header;
-use rocket::{Request, Response};
+use std::borrow::Cow;
 
-use crate::app::providers::config::getter::ConfigGetter;
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::http::{Method, Status};
+use rocket::{Build, Request, Response, Rocket};
 
+use super::settings::Settings;
+
+/// CORS for the origins in `origin_url`, where `https://*.example.com` stands for any of its
+/// subdomains. Other origins get no CORS header at all, so the browser keeps the answer from
+/// the page. Preflights are answered on every path
 pub struct Cors;
 
 #[rocket::async_trait]
 impl Fairing for Cors {
 	fn info(&self) -> Info {
 		Info {
-			name: "Add CORS headers to response",
-			kind: Kind::Response,
+			name: "CORS",
+			kind: Kind::Ignite | Kind::Response,
 		}
 	}
 
+	async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+		Ok(rocket.mount("/", routes![preflight]))
+	}
+
 	async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
-		let c_origin = request.headers().get_one("Origin");
-		// let origins = ConfigGetter::get_origin_url().unwrap_or("*".to_string()).to_string();
-		let origins = ConfigGetter::get_origin_url();
-
-		let allowed_origins = if origins.contains(',') {
-			origins.split(',').collect::<Vec<&str>>()
-		} else {
-			vec![&*origins]
+		let settings = match request.rocket().state::<Settings>() {
+			Some(settings) => settings,
+			None => return,
 		};
 
-		let origin = match c_origin {
-			Some(claimed_origin) if allowed_origins.contains(&claimed_origin) => claimed_origin,
-			_ => "*",
+		// the answer depends on the origin, caches must not share it between origins
+		response.adjoin_raw_header("Vary", "Origin");
+
+		let origin = match request.headers().get_one("Origin") {
+			Some(origin) if is_allowed(&settings.origins, origin) => origin,
+			_ => return,
 		};
 
-		response.set_header(Header::new("Access-Control-Allow-Origin", origin));
-		response.set_header(Header::new(
-			"Access-Control-Allow-Methods",
-			"POST, GET, PUT, PATCH, OPTIONS",
-		));
-		response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
-		response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+		let cors = &settings.cors;
+		response.set_raw_header("Access-Control-Allow-Origin", origin);
+		response.set_raw_header("Access-Control-Allow-Credentials", "true");
+		if !cors.expose.is_empty() {
+			response.set_raw_header("Access-Control-Expose-Headers", cors.expose.join(", "));
+		}
+
+		let preflight = request.method() == Method::Options
+			&& request.headers().contains("Access-Control-Request-Method");
+		if preflight {
+			response.set_raw_header("Access-Control-Allow-Methods", cors.methods.join(", "));
+			response.set_raw_header("Access-Control-Allow-Headers", cors.headers.join(", "));
+			response.set_raw_header("Access-Control-Max-Age", cors.max_age.to_string());
+		}
+	}
+}
+
+/// Any path, the fairing adds the headers. Ranked last so a route of its own takes over
+#[options("/<_..>", rank = 100)]
+fn preflight() -> Status {
+	Status::NoContent
+}
+
+/// The origin is one of `origins`, or a subdomain of a wildcard one
+pub fn is_allowed(origins: &[Cow<'static, str>], origin: &str) -> bool {
+	origins.iter().any(|pattern| matches(pattern, origin))
+}
+
+fn matches(pattern: &str, origin: &str) -> bool {
+	let pattern = pattern.trim_end_matches('/');
+	let (scheme, domain) = match pattern.split_once("://*.") {
+		Some(wildcard) => wildcard,
+		None => return pattern.eq_ignore_ascii_case(origin),
+	};
+
+	let host = match origin.split_once("://") {
+		Some((claimed, host)) if claimed.eq_ignore_ascii_case(scheme) => {
+			host.to_ascii_lowercase()
+		}
+		_ => return false,
+	};
+
+	// one label at least, the domain itself is not one of its subdomains
+	match host.strip_suffix(&format!(".{}", domain.to_ascii_lowercase())) {
+		Some(sub) => {
+			!sub.is_empty()
+				&& sub.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+		}
+		None => false,
 	}
 }
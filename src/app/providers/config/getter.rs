@@ -2,43 +2,206 @@ use std::borrow::Cow;
 
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct DatabaseConfig {
 	pub host: Cow<'static, str>,
 	pub port: u16,
 	pub namespace: Cow<'static, str>,
 	pub database: Cow<'static, str>,
+	/// Where the user is defined, a namespace or a database user needs no root access
+	#[serde(default)]
+	pub signin: Signin,
+	#[serde(default)]
 	pub username: Cow<'static, str>,
+	#[serde(default)]
 	pub password: Cow<'static, str>,
+	#[serde(default)]
+	pub retry: RetryConfig,
+	#[serde(default)]
+	pub pool: PoolConfig,
+	#[serde(default)]
+	pub migrations: MigrationsConfig,
+	/// Endpoint for the queries that only read, the main one when missing
+	pub read: Option<EndpointConfig>,
 }
 
-impl DatabaseConfig {
-	pub fn get_database_config(name: &str) -> Option<DatabaseConfig> {
-		let name = format!("databases.{}", name);
-		rocket::Config::figment().extract_inner::<DatabaseConfig>(name.as_str()).ok()
-	}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Signin {
+	#[default]
+	Root,
+	Namespace,
+	Database,
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(crate = "rocket::serde")]
-pub struct ConfigGetter {
-	pub origin_url: String,
-	pub secret_key: String,
+pub struct EndpointConfig {
+	pub host: Cow<'static, str>,
+	pub port: u16,
+}
+
+/// Connections opened to every endpoint, each one runs a single query at a time
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct PoolConfig {
+	pub size: usize,
+}
+
+impl Default for PoolConfig {
+	fn default() -> Self {
+		PoolConfig {
+			size: 4,
+		}
+	}
+}
+
+/// Pending schema migrations are applied at startup unless `auto` is off, then they are left
+/// to `q-api-auth migrate`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct MigrationsConfig {
+	pub auto: bool,
+	/// Goes on when an applied migration no longer matches its file instead of failing
+	pub allow_changed: bool,
+}
+
+impl Default for MigrationsConfig {
+	fn default() -> Self {
+		MigrationsConfig {
+			auto: true,
+			allow_changed: false,
+		}
+	}
+}
+
+/// Connection upkeep, every duration in seconds
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct RetryConfig {
+	/// Connection attempts at startup, 0 keeps trying
+	pub attempts: u32,
+	/// Wait before the second attempt, doubled after every failure up to `max_backoff`
+	pub backoff: u64,
+	pub max_backoff: u64,
+	/// Time between checks of the running connection (0 disables them)
+	pub check: u64,
+	/// Longest a query, a check or a connection attempt may take
+	pub timeout: u64,
+}
+
+impl Default for RetryConfig {
+	fn default() -> Self {
+		RetryConfig {
+			attempts: 0,
+			backoff: 1,
+			max_backoff: 30,
+			check: 5,
+			timeout: 10,
+		}
+	}
+}
+
+/// CORS policy for the allowed origins, `max_age` in seconds a preflight answer is kept
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct CorsConfig {
+	pub methods: Vec<Cow<'static, str>>,
+	/// Request headers the page may send
+	pub headers: Vec<Cow<'static, str>>,
+	/// Response headers the page may read
+	pub expose: Vec<Cow<'static, str>>,
+	pub max_age: u64,
 }
 
-impl ConfigGetter {
-	pub fn get_origin_url() -> String {
-		rocket::Config::figment()
-			.extract::<ConfigGetter>()
-			.expect("Failed to get the origin url")
-			.origin_url
+impl Default for CorsConfig {
+	fn default() -> Self {
+		CorsConfig {
+			methods: ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"].map(Cow::from).into(),
+			headers: ["Authorization", "Content-Type", "X-Request-Id"].map(Cow::from).into(),
+			expose: vec!["X-Request-Id".into()],
+			max_age: 3600,
+		}
 	}
+}
+
+/// Background consistency check, `interval` in seconds (0 disables it)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct ConsistencyConfig {
+	pub interval: u64,
+	pub fix: bool,
+}
+
+impl Default for ConsistencyConfig {
+	fn default() -> Self {
+		ConsistencyConfig {
+			interval: 3600,
+			fix: false,
+		}
+	}
+}
+
+/// Days the audit entries are kept (0 keeps them forever)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct AuditConfig {
+	pub retention: u64,
+}
+
+impl Default for AuditConfig {
+	fn default() -> Self {
+		AuditConfig {
+			retention: 365,
+		}
+	}
+}
+
+/// Webhook delivery worker, every duration in seconds (`poll` 0 disables it)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct WebhooksConfig {
+	pub poll: u64,
+	pub timeout: u64,
+	/// Failed deliveries wait `backoff`, then twice as long after every new failure
+	pub backoff: u64,
+	/// Attempts before the delivery goes to the dead letters
+	pub max_attempts: u32,
+}
+
+impl Default for WebhooksConfig {
+	fn default() -> Self {
+		WebhooksConfig {
+			poll: 5,
+			timeout: 10,
+			backoff: 30,
+			max_attempts: 8,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum LogFormat {
+	#[default]
+	Pretty,
+	Json,
+}
+
+/// Log output, `RUST_LOG` takes over `level` when set
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", default)]
+pub struct LoggingConfig {
+	pub format: LogFormat,
+	pub level: Cow<'static, str>,
+}
 
-	pub fn get_secret_key() -> String {
-		rocket::Config::figment()
-			.extract::<ConfigGetter>()
-			.expect("Failed to get the secret key")
-			.secret_key
+impl Default for LoggingConfig {
+	fn default() -> Self {
+		LoggingConfig {
+			format: LogFormat::Pretty,
+			level: "info".into(),
+		}
 	}
 }
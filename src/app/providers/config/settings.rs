@@ -0,0 +1,222 @@
+use std::borrow::Cow;
+
+use rocket::config::SecretKey;
+use rocket::figment::providers::{Env, Serialized};
+use rocket::figment::Figment;
+use rocket::http::Method;
+use rocket::serde::{Deserialize, Deserializer};
+
+use crate::app::providers::services::auth::link;
+
+use super::getter::{
+	AuditConfig, ConsistencyConfig, CorsConfig, DatabaseConfig, LoggingConfig, WebhooksConfig,
+};
+
+/// Settings holding a secret, each one is read from the file its `_file` variant names when
+/// set, as the secrets Docker and Podman mount under `/run/secrets`
+const SECRETS: [&str; 3] =
+	["secret_key", "databases.store.username", "databases.store.password"];
+
+const MIN_TOKEN_TTL: u64 = 60;
+const MAX_TOKEN_TTL: u64 = 30 * 24 * 3600;
+
+/// Everything read from the configuration, extracted and checked once at startup and then
+/// managed as state. A bad value stops the launch instead of failing the first request
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Settings {
+	/// Signs the global tokens, the database checks them with it too
+	#[serde(default)]
+	pub secret_key: Cow<'static, str>,
+	/// Allowed CORS origins, comma separated as `origin_url`. `https://*.example.com` allows
+	/// its subdomains
+	#[serde(rename = "origin_url", deserialize_with = "comma_separated")]
+	pub origins: Vec<Cow<'static, str>>,
+	/// Seconds a token is valid for
+	#[serde(default = "default_token_ttl")]
+	pub token_ttl: u64,
+	pub databases: Databases,
+	#[serde(default)]
+	pub cors: CorsConfig,
+	#[serde(default)]
+	pub consistency: ConsistencyConfig,
+	#[serde(default)]
+	pub audit: AuditConfig,
+	#[serde(default)]
+	pub webhooks: WebhooksConfig,
+	#[serde(default)]
+	pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Databases {
+	pub store: DatabaseConfig,
+}
+
+fn default_token_ttl() -> u64 {
+	24 * 3600
+}
+
+fn comma_separated<'de, D>(deserializer: D) -> Result<Vec<Cow<'static, str>>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let list = String::deserialize(deserializer)?;
+
+	Ok(list
+		.split(',')
+		.map(str::trim)
+		.filter(|origin| !origin.is_empty())
+		.map(|origin| Cow::Owned(origin.to_owned()))
+		.collect())
+}
+
+impl Settings {
+	/// Rocket's figment, where `ROCKET_A__B` also sets the nested `a.b`, as
+	/// `ROCKET_DATABASES__STORE__PASSWORD_FILE`
+	pub fn figment() -> Figment {
+		let nested =
+			Env::prefixed("ROCKET_").filter(|key| key.as_str().contains("__")).split("__");
+
+		rocket::Config::figment().merge(nested.global())
+	}
+
+	/// The figment Rocket is built from too, so both see the secrets read from files. Panics
+	/// listing every problem found
+	pub fn load() -> (Figment, Settings) {
+		let loaded = Settings::read_secrets(Settings::figment()).and_then(|figment| {
+			let settings = Settings::extract(&figment)?;
+			Ok((figment, settings))
+		});
+
+		match loaded {
+			Ok(loaded) => loaded,
+			Err(problems) => panic!("Invalid configuration:\n  - {}", problems.join("\n  - ")),
+		}
+	}
+
+	/// Replaces every secret with the content of its file, the file wins over the value
+	pub fn read_secrets(mut figment: Figment) -> Result<Figment, Vec<String>> {
+		let mut problems = Vec::new();
+
+		for secret in SECRETS {
+			let key = format!("{secret}_file");
+			let path = match figment.extract_inner::<String>(&key) {
+				Ok(path) if !path.is_empty() => path,
+				_ => continue,
+			};
+
+			match std::fs::read_to_string(&path) {
+				Ok(value) => {
+					let value = value.trim_end_matches(['\r', '\n']).to_owned();
+					figment = figment.merge(Serialized::global(secret, value));
+				}
+				Err(e) => problems.push(format!("{key}: cannot read `{path}`, {e}")),
+			}
+		}
+
+		match problems.is_empty() {
+			true => Ok(figment),
+			false => Err(problems),
+		}
+	}
+
+	pub fn extract(figment: &Figment) -> Result<Settings, Vec<String>> {
+		let settings: Settings = figment
+			.extract()
+			.map_err(|e| e.into_iter().map(|e| e.to_string()).collect::<Vec<_>>())?;
+
+		let problems = settings.problems();
+		if !problems.is_empty() {
+			return Err(problems);
+		}
+
+		Ok(settings)
+	}
+
+	fn problems(&self) -> Vec<String> {
+		let mut problems = Vec::new();
+
+		if !is_secret_key(&self.secret_key) {
+			problems.push(
+				"secret_key must be 32 or 64 bytes in base64 or hex, `openssl rand -base64 32` \
+				makes one"
+					.to_owned(),
+			);
+		}
+
+		if self.origins.is_empty() {
+			problems.push("origin_url lists no origin".to_owned());
+		}
+		for origin in self.origins.iter().filter(|origin| !is_origin(origin)) {
+			problems.push(format!(
+				"origin_url: `{origin}` is not an origin, as https://example.com"
+			));
+		}
+
+		let methods = self.cors.methods.iter();
+		for method in methods.filter(|method| method.parse::<Method>().is_err()) {
+			problems.push(format!("cors.methods: `{method}` is not an HTTP method"));
+		}
+
+		if !(MIN_TOKEN_TTL..=MAX_TOKEN_TTL).contains(&self.token_ttl) {
+			problems.push(format!(
+				"token_ttl must be between {MIN_TOKEN_TTL} and {MAX_TOKEN_TTL} seconds"
+			));
+		}
+
+		let store = &self.databases.store;
+		if !link::is_embedded(store) && store.username.is_empty() {
+			problems.push(
+				"databases.store.username is missing, or username_file, to sign in".to_owned(),
+			);
+		}
+		if store.pool.size == 0 {
+			problems.push("databases.store.pool.size must be at least 1".to_owned());
+		}
+		if store.retry.timeout == 0 {
+			problems.push("databases.store.retry.timeout must be at least 1 second".to_owned());
+		}
+		if store.retry.backoff > store.retry.max_backoff {
+			problems
+				.push("databases.store.retry.backoff is longer than max_backoff".to_owned());
+		}
+
+		if self.webhooks.timeout == 0 {
+			problems.push("webhooks.timeout must be at least 1 second".to_owned());
+		}
+		if self.webhooks.max_attempts == 0 {
+			problems.push("webhooks.max_attempts must be at least 1".to_owned());
+		}
+
+		problems
+	}
+}
+
+/// As Rocket reads it for its private cookies, it refuses to start otherwise. Shorter keys
+/// would make the HS256 tokens easy to forge too
+fn is_secret_key(secret_key: &str) -> bool {
+	Figment::from(Serialized::global("secret_key", secret_key))
+		.extract_inner::<SecretKey>("secret_key")
+		.is_ok()
+}
+
+/// Scheme, host and optional port, nothing after them. The host may start with `*.`
+fn is_origin(origin: &str) -> bool {
+	// checked with a subdomain in place of the wildcard
+	let origin = match origin.split_once("://*.") {
+		Some((scheme, domain)) => Cow::Owned(format!("{scheme}://sub.{domain}")),
+		None => Cow::Borrowed(origin),
+	};
+
+	// the parser takes a `*` anywhere in the host as a plain character
+	match reqwest::Url::parse(&origin) {
+		Ok(_) if origin.contains('*') => false,
+		Ok(url) => {
+			matches!(url.scheme(), "http" | "https")
+				&& url.origin().ascii_serialization() == origin.trim_end_matches('/')
+		}
+		Err(_) => false,
+	}
+}
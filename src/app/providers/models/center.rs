@@ -3,9 +3,41 @@ use std::borrow::Cow;
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
+use super::user::{Role, UnknownRole};
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Center {
 	pub id: Thing,
 	pub name: Cow<'static, str>,
 }
+
+/// A `roled` edge seen from the user: the center and the role held there
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CenterRole {
+	pub id: Thing,
+	pub name: Cow<'static, str>,
+	pub role: Role,
+}
+
+/// A `roled` edge as stored, its role not checked yet
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CenterRolePrev {
+	pub id: Thing,
+	pub name: Cow<'static, str>,
+	pub role: Cow<'static, str>,
+}
+
+impl TryFrom<CenterRolePrev> for CenterRole {
+	type Error = UnknownRole;
+
+	fn try_from(center: CenterRolePrev) -> Result<Self, Self::Error> {
+		Ok(CenterRole {
+			role: center.role.try_into()?,
+			id: center.id,
+			name: center.name,
+		})
+	}
+}
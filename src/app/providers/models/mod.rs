@@ -1,3 +1,5 @@
 pub mod center;
+pub mod permission;
 pub mod project;
+pub mod record;
 pub mod user;
@@ -0,0 +1,15 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+use super::user::Role;
+
+/// Permission set of a role, for every project when `project` is none
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RolePerms {
+	pub role: Role,
+	pub project: Option<Thing>,
+	pub perms: Vec<Cow<'static, str>>,
+}
@@ -11,4 +11,29 @@ pub struct Project {
 	pub state: Cow<'static, str>,
 	pub token: Cow<'static, str>,
 	pub center: Thing,
+	#[serde(default)]
+	pub signup: SignupMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum ProjectState {
+	Active,
+	Inactive,
+	Finished,
+}
+
+/// How users get into a project, projects without one only take invitations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum SignupMode {
+	/// Anyone knowing the project id
+	Open,
+	/// Only with an invitation code
+	#[default]
+	Invite,
+	/// Anyone, but a coordinator approves the membership first
+	Approval,
+	/// Nobody, not even with an invitation
+	Closed,
 }
@@ -0,0 +1,55 @@
+use std::fmt;
+
+use rocket::http::Status;
+use surrealdb::sql::{Id, Thing};
+use tracing::debug;
+
+/// Record id that doesn't parse or belongs to another table
+#[derive(Debug)]
+pub struct BadRecordId(pub String);
+
+impl fmt::Display for BadRecordId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "bad record id: {}", self.0)
+	}
+}
+
+impl From<BadRecordId> for Status {
+	fn from(error: BadRecordId) -> Self {
+		debug!(id = error.0, "Bad record id");
+		Status::UnprocessableEntity
+	}
+}
+
+/// Parses `table:key` the way SurrealDB writes it: numeric keys stay numbers, escaped keys
+/// (`⟨...⟩` or backticks) keep their content and bare UUIDs are taken as strings
+pub fn parse_record(id: &str, table: &str) -> Result<Thing, BadRecordId> {
+	let bad = || BadRecordId(id.to_owned());
+
+	let (tb, key) = id.trim().split_once(':').ok_or_else(bad)?;
+	if tb != table || key.is_empty() {
+		return Err(bad());
+	}
+
+	match id.trim().parse::<Thing>() {
+		Ok(thing) if thing.tb == table => Ok(thing),
+		Ok(_) => Err(bad()),
+		// the parser only takes hyphens inside brackets
+		Err(_) if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') => {
+			Ok(Thing::from((table, Id::String(key.to_owned()))))
+		}
+		Err(_) => Err(bad()),
+	}
+}
+
+/// Same as [`parse_record`] but also takes the key alone, as route paths carry it
+pub fn parse_key(key: &str, table: &str) -> Result<Thing, BadRecordId> {
+	match key.split_once(':') {
+		Some((tb, _)) if tb == table => parse_record(key, table),
+		_ => parse_record(&format!("{table}:{key}"), table),
+	}
+}
+
+pub fn parse_optional(id: Option<&str>, table: &str) -> Result<Option<Thing>, BadRecordId> {
+	id.map(|id| parse_record(id, table)).transpose()
+}
@@ -1,11 +1,13 @@
 use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
 
 use rocket::serde::json::Value;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(crate = "rocket::serde")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
 pub enum Role {
 	Robot,
 	Admin,
@@ -15,44 +17,74 @@ pub enum Role {
 	Guest,
 }
 
+impl Role {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Role::Robot => "robot",
+			Role::Admin => "admin",
+			Role::Coord => "coord",
+			Role::Thera => "thera",
+			Role::Parti => "parti",
+			Role::Guest => "guest",
+		}
+	}
+
+	/// Position in the hierarchy Admin > Coord > Thera > Parti > Guest, robots stand apart
+	fn rank(&self) -> Option<u8> {
+		match self {
+			Role::Robot => None,
+			Role::Admin => Some(4),
+			Role::Coord => Some(3),
+			Role::Thera => Some(2),
+			Role::Parti => Some(1),
+			Role::Guest => Some(0),
+		}
+	}
+
+	pub fn is_at_least(&self, other: Role) -> bool {
+		match (self.rank(), other.rank()) {
+			(Some(rank), Some(other)) => rank >= other,
+			_ => *self == other,
+		}
+	}
+}
+
 impl From<Role> for Cow<'static, str> {
 	fn from(role: Role) -> Self {
+		Cow::Borrowed(role.as_str())
+	}
+}
+
+impl FromStr for Role {
+	type Err = UnknownRole;
+
+	fn from_str(role: &str) -> Result<Self, Self::Err> {
 		match role {
-			Role::Robot => Cow::Borrowed("robot"),
-			Role::Admin => Cow::Borrowed("admin"),
-			Role::Coord => Cow::Borrowed("coord"),
-			Role::Thera => Cow::Borrowed("thera"),
-			Role::Parti => Cow::Borrowed("parti"),
-			Role::Guest => Cow::Borrowed("guest"),
+			"robot" => Ok(Role::Robot),
+			"admin" => Ok(Role::Admin),
+			"coord" => Ok(Role::Coord),
+			"thera" => Ok(Role::Thera),
+			"parti" => Ok(Role::Parti),
+			"guest" => Ok(Role::Guest),
+			_ => Err(UnknownRole(role.to_string())),
 		}
 	}
 }
 
-impl From<String> for Role {
-	fn from(role: String) -> Self {
-		match role.as_ref() {
-			"robot" => Role::Robot,
-			"admin" => Role::Admin,
-			"coord" => Role::Coord,
-			"thera" => Role::Thera,
-			"parti" => Role::Parti,
-			"guest" => Role::Guest,
-			_ => Role::Parti,
-		}
+impl TryFrom<Cow<'static, str>> for Role {
+	type Error = UnknownRole;
+
+	fn try_from(role: Cow<'static, str>) -> Result<Self, Self::Error> {
+		role.parse()
 	}
 }
 
-impl From<Cow<'static, str>> for Role {
-	fn from(role: Cow<'static, str>) -> Self {
-		match role.as_ref() {
-			"robot" => Role::Robot,
-			"admin" => Role::Admin,
-			"coord" => Role::Coord,
-			"thera" => Role::Thera,
-			"parti" => Role::Parti,
-			"guest" => Role::Guest,
-			_ => Role::Parti,
-		}
+#[derive(Debug)]
+pub struct UnknownRole(pub String);
+
+impl fmt::Display for UnknownRole {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "unknown role: {}", self.0)
 	}
 }
 
@@ -65,6 +97,10 @@ pub struct UserGlobalPrev {
 	// pub role: Cow<'static, str>,
 	pub project: Option<Thing>,
 	pub web_token: Value,
+	pub password_reset: Option<bool>,
+	/// Project the user asked to join and is still waiting for
+	pub pending: Option<Thing>,
+	pub state: Option<UserState>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,6 +112,10 @@ pub struct UserGlobal {
 	// pub role: Role,
 	pub project: Option<Thing>,
 	pub web_token: Value,
+	pub password_reset: Option<bool>,
+	/// Project the user asked to join and is still waiting for
+	pub pending: Option<Thing>,
+	pub state: Option<UserState>,
 }
 
 #[derive(Deserialize)]
@@ -95,19 +135,23 @@ pub struct UserInterv {
 	pub state: Cow<'static, str>,
 }
 
-impl From<UserIntervPrev> for UserInterv {
-	fn from(user: UserIntervPrev) -> UserInterv {
-		UserInterv {
+impl TryFrom<UserIntervPrev> for UserInterv {
+	type Error = UnknownRole;
+
+	fn try_from(user: UserIntervPrev) -> Result<UserInterv, Self::Error> {
+		Ok(UserInterv {
 			id: user.id,
 			// pass: user.pass,
-			role: user.role.into(),
-			state: user.state.into(),
-		}
+			role: user.role.try_into()?,
+			state: user.state,
+		})
 	}
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
 pub enum UserState {
+	#[default]
 	Active,
 	Exited,
 	Standby,
@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+use std::convert::Infallible;
+
+use rocket::request::{FromRequest, Outcome, Request};
+use surrealdb::sql::Uuid;
+
+use crate::app::providers::services::auth::claims::Claims;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Where a request comes from, written with every audit entry
+#[derive(Debug, Clone, Default)]
+pub struct RequestMeta {
+	pub ip: Option<Cow<'static, str>>,
+	pub user_agent: Option<Cow<'static, str>>,
+	/// Taken from `X-Request-Id` when the caller sends one
+	pub request_id: Cow<'static, str>,
+}
+
+impl RequestMeta {
+	pub fn from_request(request: &Request<'_>) -> RequestMeta {
+		request
+			.local_cache(|| {
+				let request_id = match request.headers().get_one(REQUEST_ID_HEADER) {
+					Some(id) if !id.is_empty() && id.len() <= 64 => id.to_owned(),
+					_ => Uuid::new_v7().to_raw(),
+				};
+
+				RequestMeta {
+					ip: request.client_ip().map(|ip| ip.to_string().into()),
+					user_agent: request
+						.headers()
+						.get_one("User-Agent")
+						.map(|ua| ua.to_owned().into()),
+					request_id: request_id.into(),
+				}
+			})
+			.clone()
+	}
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RequestMeta {
+	type Error = Infallible;
+
+	async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+		Outcome::Success(RequestMeta::from_request(request))
+	}
+}
+
+/// Who does an action and from where
+pub struct Actor<'a> {
+	pub claims: &'a Claims,
+	pub meta: &'a RequestMeta,
+}
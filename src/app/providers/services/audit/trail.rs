@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use chrono::Utc;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::json::Value;
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+use tracing::error;
+
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::webhooks::delivery;
+
+use super::request::{Actor, RequestMeta};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Outcome {
+	Success,
+	Failure,
+}
+
+/// One authentication event or administrative action, appended to the `audit` table
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AuditEntry {
+	pub at: Datetime,
+	pub actor: Option<Thing>,
+	pub action: Cow<'static, str>,
+	pub target: Option<Thing>,
+	pub project: Option<Thing>,
+	pub outcome: Outcome,
+	/// Status sent back when the action failed
+	pub status: Option<u16>,
+	pub ip: Option<Cow<'static, str>>,
+	pub user_agent: Option<Cow<'static, str>>,
+	pub request_id: Option<Cow<'static, str>>,
+	pub detail: Value,
+}
+
+impl AuditEntry {
+	pub fn new(actor: &Actor<'_>, action: &'static str, target: &Thing) -> Self {
+		let mut entry = AuditEntry::event(action, actor.meta);
+		entry.actor = actor.claims.id.parse().ok();
+		entry.target = Some(target.clone());
+		entry
+	}
+
+	/// Entry without a known actor yet, as a login before the credentials are checked
+	pub fn event(action: &'static str, meta: &RequestMeta) -> Self {
+		AuditEntry {
+			at: Datetime::default(),
+			actor: None,
+			action: action.into(),
+			target: None,
+			project: None,
+			outcome: Outcome::Success,
+			status: None,
+			ip: meta.ip.clone(),
+			user_agent: meta.user_agent.clone(),
+			request_id: Some(meta.request_id.clone()),
+			detail: Value::Null,
+		}
+	}
+
+	pub fn actor(mut self, actor: Option<Thing>) -> Self {
+		self.actor = actor;
+		self
+	}
+
+	pub fn project(mut self, project: Option<Thing>) -> Self {
+		self.project = project;
+		self
+	}
+
+	pub fn failed(mut self, status: Status) -> Self {
+		self.outcome = Outcome::Failure;
+		self.status = Some(status.code);
+		self
+	}
+
+	pub fn detail(mut self, detail: Value) -> Self {
+		self.detail = detail;
+		self
+	}
+}
+
+/// Audit failures are reported but never undo the action already done. Successful entries
+/// are also queued for the webhook subscribers
+pub async fn record(db: &DbAuth, entry: AuditEntry) {
+	let result = match db
+		.query("audit.record", "CREATE audit CONTENT $b_entry RETURN NONE;")
+		.bind(("b_entry", &entry))
+		.await
+	{
+		Ok(response) => response.check().map(|_| ()),
+		Err(e) => Err(e),
+	};
+
+	if let Err(e) = result {
+		error!(error = %e, action = %entry.action, "Error writing audit entry");
+	}
+
+	if entry.outcome == Outcome::Success {
+		if let Err(e) = delivery::enqueue(db, &entry).await {
+			error!(error = %e, "Error queueing webhooks");
+		}
+	}
+}
+
+/// Entries are never changed, the only deletion is this one, once they are older than
+/// the retention
+pub async fn prune(db: &DbAuth, days: u64) -> Result<usize, surrealdb::Error> {
+	let mut query = db
+		.query("audit.prune", "RETURN DELETE audit WHERE at < $b_before RETURN BEFORE;")
+		.bind(("b_before", Datetime::from(Utc::now() - chrono::Duration::days(days as i64))))
+		.await?;
+
+	let pruned: Vec<Value> = query.take(query.num_statements() - 1)?;
+
+	Ok(pruned.len())
+}
+
+pub fn job() -> AdHoc {
+	AdHoc::on_liftoff("Audit retention", |rocket| {
+		Box::pin(async move {
+			let config = match rocket.state::<Settings>() {
+				Some(settings) => settings.audit.clone(),
+				None => return,
+			};
+			if config.retention == 0 {
+				return;
+			}
+
+			let db = match rocket.state::<DbAuth>() {
+				Some(db) => db.clone(),
+				None => return,
+			};
+
+			rocket::tokio::spawn(async move {
+				let mut interval =
+					rocket::tokio::time::interval(Duration::from_secs(24 * 3600));
+
+				loop {
+					interval.tick().await;
+
+					if let Err(e) = prune(&db, config.retention).await {
+						error!(error = %e, "Error pruning audit entries");
+					}
+				}
+			});
+		})
+	})
+}
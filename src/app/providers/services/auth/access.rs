@@ -0,0 +1,50 @@
+use rocket::http::Status;
+use surrealdb::error::Db;
+use surrealdb::sql::Thing;
+use tracing::error;
+
+use crate::app::providers::models::user::Role;
+
+use super::claims::Claims;
+
+/// Only admins and the coordinators of the center in `$q_center` go past it, the others get
+/// `E_FORBIDDEN`. Expects the caller bound as `$b_user` and `$b_admin`
+pub const CHECK_COORD: &str = r#"
+	IF !$b_admin AND count(SELECT id FROM roled
+		WHERE in = $b_user AND out = $q_center AND role = 'coord') = 0 {
+		THROW "E_FORBIDDEN";
+	};
+"#;
+
+/// The caller as bound to `$b_user`
+pub fn user_id(claims: &Claims) -> Option<Thing> {
+	claims.id.parse().ok()
+}
+
+/// The caller as bound to `$b_admin`
+pub fn is_admin(claims: &Claims) -> bool {
+	claims.role == Some(Role::Admin)
+}
+
+/// Maps the codes thrown inside the transactions, `E_NOT_FOUND`, `E_FORBIDDEN` and `E_CONFLICT`,
+/// to their status. Any other error is logged and answered with 500
+pub fn check_errors(query: &mut surrealdb::Response) -> Result<(), Status> {
+	let errors = query.take_errors();
+	if errors.is_empty() {
+		return Ok(());
+	}
+
+	let thrown = errors.values().find_map(|e| match e {
+		surrealdb::Error::Db(Db::Thrown(code)) => Some(code.as_str()),
+		_ => None,
+	});
+	match thrown {
+		Some("E_NOT_FOUND") => Err(Status::NotFound),
+		Some("E_FORBIDDEN") => Err(Status::Forbidden),
+		Some("E_CONFLICT") => Err(Status::Conflict),
+		_ => {
+			error!(?errors, "Error in transaction");
+			Err(Status::InternalServerError)
+		}
+	}
+}
@@ -0,0 +1,47 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::Request;
+
+use super::error::AuthError;
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ErrorBody {
+	pub status: u16,
+	pub error: Option<AuthError>,
+	pub message: &'static str,
+}
+
+pub fn catchers() -> Vec<rocket::Catcher> {
+	catchers![unauthorized, forbidden, locked]
+}
+
+#[catch(401)]
+fn unauthorized(request: &Request) -> Json<ErrorBody> {
+	error_body(Status::Unauthorized, request)
+}
+
+#[catch(403)]
+fn forbidden(request: &Request) -> Json<ErrorBody> {
+	error_body(Status::Forbidden, request)
+}
+
+#[catch(423)]
+fn locked() -> Json<ErrorBody> {
+	Json(ErrorBody {
+		status: Status::Locked.code,
+		error: None,
+		message: "Account is on standby, wait until it is active again",
+	})
+}
+
+fn error_body(status: Status, request: &Request) -> Json<ErrorBody> {
+	let error = *request.local_cache(|| None::<AuthError>);
+
+	Json(ErrorBody {
+		status: status.code,
+		error,
+		message: error.map(|e| e.message()).unwrap_or(status.reason_lossy()),
+	})
+}
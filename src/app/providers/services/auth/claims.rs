@@ -4,6 +4,8 @@ use jsonwebtoken::errors::Error;
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
 
+use crate::app::providers::models::user::Role;
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Claims {
@@ -12,11 +14,24 @@ pub struct Claims {
 	pub sc: Cow<'static, str>,
 	pub tk: Cow<'static, str>,
 	pub id: Cow<'static, str>,
-	pub role: Option<Cow<'static, str>>,
+	pub role: Option<Role>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub center: Option<Cow<'static, str>>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub perms: Vec<Cow<'static, str>>,
+	/// Session the token was issued for, revoking it invalidates the token
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sid: Option<Cow<'static, str>>,
+	/// Issued after an admin reset the password, only good for changing it
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub password_reset: bool,
 	iat: i64,
 	exp: i64,
 }
 
+/// Claims of a live token, the ones issued after a password reset included
+pub struct ResetClaims(pub Claims);
+
 impl Claims {
 	pub fn new(
 		ns: Cow<'static, str>,
@@ -24,7 +39,7 @@ impl Claims {
 		sc: Cow<'static, str>,
 		tk: Cow<'static, str>,
 		id: Cow<'static, str>,
-		role: Option<Cow<'static, str>>,
+		role: Option<Role>,
 	) -> Self {
 		Self {
 			ns,
@@ -33,13 +48,18 @@ impl Claims {
 			tk,
 			id,
 			role,
+			center: None,
+			perms: Vec::new(),
+			sid: None,
+			password_reset: false,
 			iat: 0,
 			exp: 0,
 		}
 	}
-	pub fn encode_for_access(&mut self, token: &[u8]) -> Result<String, Error> {
+	/// Signs the claims, valid for `ttl` seconds from now
+	pub fn encode_for_access(&mut self, token: &[u8], ttl: u64) -> Result<String, Error> {
 		let iat = chrono::Utc::now().timestamp();
-		let exp = iat + 60 * 60 * 24; // 24 hours
+		let exp = iat + ttl as i64;
 
 		self.iat = iat;
 		self.exp = exp;
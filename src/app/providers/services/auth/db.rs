@@ -1,35 +1,304 @@
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use surrealdb::engine::remote::ws::{Client, Ws};
-use surrealdb::opt::auth::Root;
-use surrealdb::Surreal;
+use rocket::fairing::AdHoc;
+use rocket::serde::json::{json, Value};
+use rocket::serde::Serialize;
+use rocket::tokio::time::timeout;
+use surrealdb::engine::any::Any;
+use surrealdb::error::Api;
+use surrealdb::opt::auth::{Database, Namespace, Root};
+use surrealdb::opt::IntoQuery;
+use surrealdb::sql::{self, Statement};
+use surrealdb::{Response, Surreal};
 
-use crate::app::providers::config::getter::{ConfigGetter, DatabaseConfig};
+use crate::app::providers::config::getter::{DatabaseConfig, Signin};
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::models::user::Role;
+
+use crate::app::providers::services::metrics::registry::metrics;
+
+use super::link;
+use super::perms;
+use super::pool::Pool;
+
+/// Shared handle on the store, cloned by the background jobs
+#[derive(Clone)]
+pub struct DbAuth {
+	write: Arc<Pool>,
+	/// Separate endpoint for the queries that only read, when configured
+	read: Option<Arc<Pool>>,
+}
 
-pub struct DbAuth(pub Arc<Surreal<Client>>);
 impl DbAuth {
-	pub async fn new() -> Self {
-		let config =
-			DatabaseConfig::get_database_config("store").expect("Failed to obtain db config");
+	/// Keeps trying until the store answers, see `RetryConfig`
+	pub async fn new(settings: &Settings) -> Self {
+		let config = settings.databases.store.clone();
+
+		let read = match &config.read {
+			Some(endpoint) => {
+				let config = DatabaseConfig {
+					host: endpoint.host.clone(),
+					port: endpoint.port,
+					read: None,
+					..config.clone()
+				};
+				Some(Arc::new(Pool::open(config, None).await))
+			}
+			None => None,
+		};
+
+		let db = DbAuth {
+			write: Arc::new(Pool::open(config, Some(settings.secret_key.clone())).await),
+			read,
+		};
+
+		if db.write.config.migrations.auto {
+			db.migrate()
+				.await
+				.unwrap_or_else(|e| panic!("Failed to apply the migrations: {e}"));
+		}
+
+		db
+	}
+
+	/// Brings the schema up to date, returns how many migrations were applied
+	pub async fn migrate(&self) -> surrealdb::Result<usize> {
+		self.write.migrate().await
+	}
+
+	pub fn is_available(&self) -> bool {
+		self.write.is_available() && self.read.as_ref().is_none_or(|read| read.is_available())
+	}
+
+	pub fn has_read_endpoint(&self) -> bool {
+		self.read.is_some()
+	}
 
-		let db = Surreal::new::<Ws>(format!("{}:{}", &config.host, config.port).as_str())
-			.await
-			.expect("Failed to connect to the database");
+	/// Seconds a client should wait before trying again while the store is unavailable
+	pub fn retry_after(&self) -> u64 {
+		self.write.config.retry.check.max(1)
+	}
 
-		db.signin(Root {
-			username: &config.username,
-			password: &config.password,
+	/// Same as `Surreal::query` but timed, `name` labels the query in the metrics
+	pub fn query(&self, name: &'static str, query: impl IntoQuery) -> TimedQuery<'_> {
+		TimedQuery::new(&self.write, name, query)
+	}
+
+	/// For queries that only read and do not follow a write of the same request, a replica
+	/// may lag behind. Same as `query` without a read endpoint
+	pub fn read(&self, name: &'static str, query: impl IntoQuery) -> TimedQuery<'_> {
+		let pool = self.read.as_deref().unwrap_or(&self.write);
+		TimedQuery::new(pool, name, query)
+	}
+
+	pub async fn check(&self) {
+		self.write.check().await;
+		if let Some(read) = &self.read {
+			read.check().await;
+		}
+	}
+}
+
+/// Checks the connection in the background, so requests fail fast while it is down
+pub fn watch() -> AdHoc {
+	AdHoc::on_liftoff("Database link", |rocket| {
+		Box::pin(async move {
+			let db = match rocket.state::<DbAuth>() {
+				Some(db) => db.clone(),
+				None => return,
+			};
+
+			let every = db.write.config.retry.check;
+			if every == 0 {
+				return;
+			}
+
+			rocket::tokio::spawn(async move {
+				let mut interval = rocket::tokio::time::interval(Duration::from_secs(every));
+
+				loop {
+					interval.tick().await;
+					db.check().await;
+				}
+			});
 		})
-		.await
-		.expect("Failed to sign in");
+	})
+}
+
+/// Signs in, selects the namespace and database, and on the main endpoint, the one given the
+/// key, sets what the service needs in them. Safe to run again on a connection already prepared
+pub async fn prepare(
+	db: &Surreal<Any>,
+	config: &DatabaseConfig,
+	secret_key: Option<&str>,
+) -> surrealdb::Result<()> {
+	if !link::is_embedded(config) {
+		signin(db, config).await?;
+	} else if config.signin != Signin::Root && !config.username.is_empty() {
+		define_user(db, config).await?;
+		signin(db, config).await?;
+	}
+
+	db.use_ns(config.namespace.as_ref()).use_db(config.database.as_ref()).await?;
+
+	let secret_key = match secret_key {
+		Some(secret_key) => secret_key,
+		None => return Ok(()),
+	};
 
-		db.use_ns("global").use_db("main").await.expect("Failed to use the database");
+	let query =
+		format!("DEFINE TOKEN user_scope ON SCOPE user TYPE HS256 VALUE '{}';", secret_key);
+	db.query(query.as_str()).await?.check()?;
 
-		let secret_key = ConfigGetter::get_secret_key();
-		let query =
-			format!("DEFINE TOKEN user_scope ON SCOPE user TYPE HS256 VALUE '{}';", secret_key);
-		db.query(query.as_str()).await.expect("Failed to set the token");
+	seed_permissions(db).await
+}
+
+/// An embedded store starts with nobody in it, the configured user is defined there so the
+/// service runs with the same rights it has on a server
+async fn define_user(db: &Surreal<Any>, config: &DatabaseConfig) -> surrealdb::Result<()> {
+	let level = match config.signin {
+		Signin::Namespace => "NAMESPACE",
+		_ => "DATABASE",
+	};
+
+	let query = format!(
+		"DEFINE USER `{}` ON {level} PASSWORD {} ROLES OWNER;",
+		config.username,
+		json!(config.password),
+	);
+
+	db.use_ns(config.namespace.as_ref()).use_db(config.database.as_ref()).await?;
+	db.query(query.as_str()).await?.check()?;
+
+	Ok(())
+}
+
+/// A namespace or a database user still needs the OWNER role on the database, the migrations
+/// and the scope token are defined with it
+async fn signin(db: &Surreal<Any>, config: &DatabaseConfig) -> surrealdb::Result<()> {
+	let username = config.username.as_ref();
+	let password = config.password.as_ref();
+
+	match config.signin {
+		Signin::Root => {
+			db.signin(Root {
+				username,
+				password,
+			})
+			.await?;
+		}
+		Signin::Namespace => {
+			db.signin(Namespace {
+				namespace: &config.namespace,
+				username,
+				password,
+			})
+			.await?;
+		}
+		Signin::Database => {
+			db.signin(Database {
+				namespace: &config.namespace,
+				database: &config.database,
+				username,
+				password,
+			})
+			.await?;
+		}
+	}
+
+	Ok(())
+}
+
+/// A query waiting for its connection, built like the `Surreal` one
+pub struct TimedQuery<'r> {
+	pool: &'r Pool,
+	statements: surrealdb::Result<Vec<Statement>>,
+	bindings: Vec<surrealdb::Result<sql::Value>>,
+	name: &'static str,
+}
+
+impl<'r> TimedQuery<'r> {
+	fn new(pool: &'r Pool, name: &'static str, query: impl IntoQuery) -> Self {
+		TimedQuery {
+			pool,
+			statements: query.into_query(),
+			bindings: Vec::new(),
+			name,
+		}
+	}
 
-		DbAuth(Arc::new(db))
+	pub fn bind(mut self, bindings: impl Serialize) -> Self {
+		self.bindings.push(sql::to_value(bindings).map_err(surrealdb::Error::from));
+		self
 	}
 }
+
+impl<'r> IntoFuture for TimedQuery<'r> {
+	type Output = surrealdb::Result<Response>;
+	type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send + Sync + 'r>>;
+
+	fn into_future(self) -> Self::IntoFuture {
+		Box::pin(async move {
+			let checkout = self.pool.checkout().await?;
+			let member = checkout.member();
+
+			// fails at once while the connection is down instead of waiting for it
+			if !member.is_available() {
+				return Err(Api::Ws("database unavailable".to_owned()).into());
+			}
+
+			let mut query = member.client.query(self.statements?);
+			for bindings in self.bindings {
+				query = query.bind(bindings?);
+			}
+
+			let started = Instant::now();
+			let result = match timeout(self.pool.timeout(), query.into_future()).await {
+				Ok(result) => result,
+				Err(_) => Err(Api::Ws("query timed out".to_owned()).into()),
+			};
+
+			metrics().query(self.name, started.elapsed(), result.is_err());
+
+			result
+		})
+	}
+}
+
+/// Keeps the permission catalogue up to date and adds the default role permission sets
+/// unless they were already configured
+async fn seed_permissions(db: &Surreal<Any>) -> surrealdb::Result<()> {
+	let catalogue: Vec<Value> = perms::CATALOGUE
+		.iter()
+		.map(|(name, description)| json!({ "name": name, "description": description }))
+		.collect();
+
+	let defaults: Vec<Value> =
+		[Role::Admin, Role::Coord, Role::Thera, Role::Parti, Role::Guest, Role::Robot]
+			.into_iter()
+			.map(|role| json!({ "role": role, "perms": perms::defaults(role) }))
+			.collect();
+
+	db.query(
+		r#"
+		FOR $perm IN $b_catalogue {
+			UPDATE type::thing('permissions', string::replace($perm.name, ':', '_')) MERGE $perm;
+		};
+
+		FOR $row IN $b_defaults {
+			IF array::len(SELECT id FROM role_perms WHERE role = $row.role AND project = NONE) = 0 {
+				CREATE role_perms CONTENT $row;
+			};
+		};
+		"#,
+	)
+	.bind(("b_catalogue", catalogue))
+	.bind(("b_defaults", defaults))
+	.await?
+	.check()?;
+
+	Ok(())
+}
@@ -1,5 +1,27 @@
-#[derive(Debug)]
+use rocket::serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
 pub enum AuthError {
 	MissingToken,
 	InvalidToken,
+	RevokedSession,
+	MissingRole,
+	InsufficientRole,
+	MissingPermission,
+	PasswordReset,
+}
+
+impl AuthError {
+	pub fn message(&self) -> &'static str {
+		match self {
+			AuthError::MissingToken => "Authorization header is missing",
+			AuthError::InvalidToken => "Token is invalid or expired",
+			AuthError::RevokedSession => "Session was closed, log in again",
+			AuthError::MissingRole => "Token carries no role",
+			AuthError::InsufficientRole => "Role is not allowed to access this resource",
+			AuthError::MissingPermission => "Token lacks the permission for this resource",
+			AuthError::PasswordReset => "Password was reset, change it before going on",
+		}
+	}
 }
@@ -1,33 +1,126 @@
 use rocket::http::Status;
+use rocket::outcome::try_outcome;
 use rocket::request::{FromRequest, Outcome, Request};
+use tracing::error;
 
-use super::claims::Claims;
+use super::claims::{Claims, ResetClaims};
+use super::db::DbAuth;
 use super::error::AuthError;
+use super::perms::{PermMarker, RequirePerm};
+use super::roles::{AtLeast, RequireRole, RoleMarker};
+use super::session;
 use super::token::Token;
 
-use crate::app::providers::config::getter::ConfigGetter;
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::services::audit::request::RequestMeta;
+use crate::app::providers::services::logging::fairing::Subject;
 
 #[async_trait]
 impl<'r> FromRequest<'r> for Claims {
 	type Error = AuthError;
 
+	async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+		let ResetClaims(claims) = try_outcome!(request.guard::<ResetClaims>().await);
+
+		if claims.password_reset {
+			return fail(request, Status::Forbidden, AuthError::PasswordReset);
+		}
+
+		Outcome::Success(claims)
+	}
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for ResetClaims {
+	type Error = AuthError;
+
 	async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
 		let token = match Token::from_header(request) {
 			Some(token) => token,
-			None => return Outcome::Error((Status::Unauthorized, AuthError::MissingToken)),
+			None => return fail(request, Status::Unauthorized, AuthError::MissingToken),
 		};
 
-		let secret_key = ConfigGetter::get_secret_key();
-		// let secret_key = match ConfigGetter::get_secret_key() {
-		// 	None => panic!("secret_key is mandatory"),
-		// 	Some(secret_key) => secret_key,
-		// };
+		let secret_key = match request.rocket().state::<Settings>() {
+			Some(settings) => settings.secret_key.as_bytes(),
+			None => return fail(request, Status::Unauthorized, AuthError::InvalidToken),
+		};
 
-		let claims = match token.decode(secret_key.as_ref()) {
+		let claims = match token.decode(secret_key) {
 			Ok(claims) => claims.claims,
-			Err(_) => return Outcome::Error((Status::Unauthorized, AuthError::InvalidToken)),
+			Err(_) => return fail(request, Status::Unauthorized, AuthError::InvalidToken),
 		};
 
-		Outcome::Success(claims)
+		let (sid, db) = match (claims.sid.as_deref(), request.rocket().state::<DbAuth>()) {
+			(Some(sid), Some(db)) => (sid, db),
+			_ => return fail(request, Status::Unauthorized, AuthError::InvalidToken),
+		};
+
+		match session::is_live(db, sid, &claims.id).await {
+			Ok(true) => {
+				request.local_cache(|| Subject(Some(claims.id.clone())));
+				Outcome::Success(ResetClaims(claims))
+			}
+			Ok(false) => fail(request, Status::Unauthorized, AuthError::RevokedSession),
+			Err(e) => {
+				error!(error = %e, "Error checking session");
+				Outcome::Error((Status::InternalServerError, AuthError::InvalidToken))
+			}
+		}
 	}
 }
+
+#[async_trait]
+impl<'r, R: RoleMarker> FromRequest<'r> for RequireRole<R> {
+	type Error = AuthError;
+
+	async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+		let claims = try_outcome!(request.guard::<Claims>().await);
+
+		match claims.role {
+			Some(role) if role == R::ROLE => {
+				Outcome::Success(RequireRole::new(claims, RequestMeta::from_request(request)))
+			}
+			Some(_) => fail(request, Status::Forbidden, AuthError::InsufficientRole),
+			None => fail(request, Status::Forbidden, AuthError::MissingRole),
+		}
+	}
+}
+
+#[async_trait]
+impl<'r, R: RoleMarker> FromRequest<'r> for AtLeast<R> {
+	type Error = AuthError;
+
+	async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+		let claims = try_outcome!(request.guard::<Claims>().await);
+
+		match claims.role {
+			Some(role) if role.is_at_least(R::ROLE) => {
+				Outcome::Success(AtLeast::new(claims, RequestMeta::from_request(request)))
+			}
+			Some(_) => fail(request, Status::Forbidden, AuthError::InsufficientRole),
+			None => fail(request, Status::Forbidden, AuthError::MissingRole),
+		}
+	}
+}
+
+#[async_trait]
+impl<'r, P: PermMarker> FromRequest<'r> for RequirePerm<P> {
+	type Error = AuthError;
+
+	async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+		let claims = try_outcome!(request.guard::<Claims>().await);
+
+		if claims.perms.iter().any(|perm| perm == P::NAME) {
+			Outcome::Success(RequirePerm::new(claims, RequestMeta::from_request(request)))
+		} else {
+			fail(request, Status::Forbidden, AuthError::MissingPermission)
+		}
+	}
+}
+
+/// Keeps the error around so the catchers can send it as body
+fn fail<T>(request: &Request<'_>, status: Status, error: AuthError) -> Outcome<T, AuthError> {
+	request.local_cache(|| Some(error));
+
+	Outcome::Error((status, error))
+}
@@ -0,0 +1,131 @@
+use std::io::Cursor;
+use std::time::Duration;
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json;
+use rocket::tokio::time::{sleep, timeout};
+use rocket::{Request, Response};
+use surrealdb::engine::any::{self, Any};
+use surrealdb::Surreal;
+use tracing::warn;
+
+use crate::app::providers::config::getter::DatabaseConfig;
+
+use super::catchers::ErrorBody;
+use super::db::{self, DbAuth};
+
+/// Where the store lives. A `host` with a scheme is taken as it is, as `mem://` or
+/// `rocksdb://path` for an embedded store, a bare host name is a server on `port`
+pub fn address(config: &DatabaseConfig) -> String {
+	match config.host.contains("://") {
+		true => config.host.to_string(),
+		false => format!("ws://{}:{}", config.host, config.port),
+	}
+}
+
+/// The store runs inside the process, there is no server to sign in to nor to lose
+pub fn is_embedded(config: &DatabaseConfig) -> bool {
+	!matches!(scheme(config).as_str(), "ws" | "wss")
+}
+
+fn scheme(config: &DatabaseConfig) -> String {
+	let address = address(config);
+	address.split_once("://").map(|(scheme, _)| scheme).unwrap_or_default().to_owned()
+}
+
+/// Embedded engines are only there when built with their feature, no retry would fix that
+fn check_engine(config: &DatabaseConfig) {
+	let (enabled, feature) = match scheme(config).as_str() {
+		"ws" | "wss" => (true, ""),
+		"mem" => (cfg!(feature = "mem"), "mem"),
+		"rocksdb" => (cfg!(feature = "rocksdb"), "rocksdb"),
+		scheme => panic!("Unsupported database engine `{scheme}`"),
+	};
+
+	if !enabled {
+		panic!("The `{}` database engine needs the `{feature}` feature", address(config));
+	}
+}
+
+/// Opens and prepares the connection, waiting longer after every failed attempt. Gives up
+/// only when `attempts` is set, the store is often started at the same time as we are
+pub async fn connect(config: &DatabaseConfig, secret_key: Option<&str>) -> Surreal<Any> {
+	check_engine(config);
+
+	let retry = &config.retry;
+	let mut wait = Duration::from_secs(retry.backoff);
+	let mut attempt = 1;
+
+	loop {
+		let opening = timeout(Duration::from_secs(retry.timeout), open(config, secret_key));
+		let error = match opening.await {
+			Ok(Ok(db)) => return db,
+			Ok(Err(e)) => e.to_string(),
+			Err(_) => "timed out".to_owned(),
+		};
+
+		if retry.attempts != 0 && attempt >= retry.attempts {
+			panic!("Failed to connect to the database: {error}");
+		}
+
+		warn!(
+			address = address(config),
+			attempt,
+			error,
+			wait_secs = wait.as_secs(),
+			"Database not ready, retrying"
+		);
+
+		sleep(wait).await;
+		wait = (wait * 2).min(Duration::from_secs(retry.max_backoff.max(retry.backoff)));
+		attempt += 1;
+	}
+}
+
+async fn open(
+	config: &DatabaseConfig,
+	secret_key: Option<&str>,
+) -> surrealdb::Result<Surreal<Any>> {
+	let db = any::connect(address(config)).await?;
+	db::prepare(&db, config, secret_key).await?;
+
+	Ok(db)
+}
+
+/// Answers 503 instead of 500 while the store is unreachable, the handlers cannot tell a
+/// lost connection from any other failed query
+pub struct Unavailable;
+
+#[rocket::async_trait]
+impl Fairing for Unavailable {
+	fn info(&self) -> Info {
+		Info {
+			name: "Database unavailable",
+			kind: Kind::Response,
+		}
+	}
+
+	async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+		if response.status() != Status::InternalServerError {
+			return;
+		}
+
+		let db = match request.rocket().state::<DbAuth>() {
+			Some(db) if !db.is_available() => db,
+			_ => return,
+		};
+
+		let body = json::to_string(&ErrorBody {
+			status: Status::ServiceUnavailable.code,
+			error: None,
+			message: "Database unavailable, try again later",
+		})
+		.unwrap_or_default();
+
+		response.set_status(Status::ServiceUnavailable);
+		response.set_header(ContentType::JSON);
+		response.set_header(Header::new("Retry-After", db.retry_after().to_string()));
+		response.set_sized_body(body.len(), Cursor::new(body));
+	}
+}
@@ -1,5 +1,12 @@
+pub mod access;
+pub mod catchers;
 pub mod claims;
 pub mod db;
 pub mod error;
 pub mod guard;
+pub mod link;
+pub mod perms;
+pub mod pool;
+pub mod roles;
+pub mod session;
 pub mod token;
@@ -0,0 +1,84 @@
+use std::marker::PhantomData;
+
+use crate::app::providers::models::user::Role;
+use crate::app::providers::services::audit::request::{Actor, RequestMeta};
+
+use super::claims::Claims;
+
+/// Type level permission, used as parameter of the permission guard
+pub trait PermMarker: Send + Sync + 'static {
+	const NAME: &'static str;
+}
+
+macro_rules! permissions {
+	($($marker:ident => $name:literal, $description:literal;)*) => {
+		$(
+			pub struct $marker;
+
+			impl PermMarker for $marker {
+				const NAME: &'static str = $name;
+			}
+		)*
+
+		/// Every permission known by the service, seeded into the `permissions` table
+		pub const CATALOGUE: &[(&str, &str)] = &[$(($name, $description)),*];
+	};
+}
+
+permissions! {
+	UsersRead => "users:read", "Read users and their memberships";
+	UsersManage => "users:manage", "Create, disable and delete users";
+	ProjectsRead => "projects:read", "Read projects";
+	ProjectsManage => "projects:manage", "Create and change projects";
+	CentersRead => "centers:read", "Read centers and their staff";
+	CentersManage => "centers:manage", "Create and change centers and their staff";
+	AnswersRead => "answers:read", "Read participant answers";
+	AnswersWrite => "answers:write", "Write answers";
+}
+
+/// Permissions of each role when the project does not configure its own
+pub fn defaults(role: Role) -> &'static [&'static str] {
+	match role {
+		Role::Admin => &[
+			"users:read",
+			"users:manage",
+			"projects:read",
+			"projects:manage",
+			"centers:read",
+			"centers:manage",
+			"answers:read",
+			"answers:write",
+		],
+		Role::Coord => {
+			&["users:read", "users:manage", "projects:read", "centers:read", "answers:read"]
+		}
+		Role::Thera => &["users:read", "projects:read", "answers:read"],
+		Role::Parti => &["answers:write"],
+		Role::Guest => &[],
+		Role::Robot => &["answers:read"],
+	}
+}
+
+/// Succeeds when the token carries the permission `P`
+pub struct RequirePerm<P: PermMarker> {
+	pub claims: Claims,
+	pub meta: RequestMeta,
+	perm: PhantomData<P>,
+}
+
+impl<P: PermMarker> RequirePerm<P> {
+	pub fn new(claims: Claims, meta: RequestMeta) -> Self {
+		RequirePerm {
+			claims,
+			meta,
+			perm: PhantomData,
+		}
+	}
+
+	pub fn actor(&self) -> Actor<'_> {
+		Actor {
+			claims: &self.claims,
+			meta: &self.meta,
+		}
+	}
+}
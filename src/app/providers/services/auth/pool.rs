@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::future::IntoFuture;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use rocket::tokio::sync::{Semaphore, SemaphorePermit};
+use rocket::tokio::time::timeout;
+use surrealdb::engine::any::Any;
+use surrealdb::error::Api;
+use surrealdb::Surreal;
+use tracing::{info, warn};
+
+use crate::app::providers::config::getter::DatabaseConfig;
+use crate::app::providers::services::schema::migrations;
+
+use super::db;
+use super::link;
+
+/// Connections to one endpoint. A query takes one for itself and gives it back once done,
+/// queries waiting for a free connection are served in arrival order
+pub struct Pool {
+	pub(super) config: DatabaseConfig,
+	/// Only the main endpoint gets the token signed with it and the permissions set up
+	secret_key: Option<Cow<'static, str>>,
+	members: Vec<Member>,
+	idle: Mutex<VecDeque<usize>>,
+	permits: Semaphore,
+}
+
+pub struct Member {
+	pub client: Surreal<Any>,
+	/// As seen by the last check
+	available: AtomicBool,
+}
+
+/// A connection taken from the pool, back in it when dropped
+pub struct Checkout<'a> {
+	pool: &'a Pool,
+	index: usize,
+	_permit: SemaphorePermit<'a>,
+}
+
+impl Pool {
+	pub async fn open(config: DatabaseConfig, secret_key: Option<Cow<'static, str>>) -> Self {
+		let size = config.pool.size.max(1);
+
+		let embedded = link::is_embedded(&config);
+
+		// an embedded store opened twice would be two stores, its members share one client
+		let mut clients = vec![link::connect(&config, secret_key.as_deref()).await];
+		for _ in 1..size {
+			let client = match embedded {
+				true => clients[0].clone(),
+				false => link::connect(&config, secret_key.as_deref()).await,
+			};
+			clients.push(client);
+		}
+
+		let members = clients
+			.into_iter()
+			.map(|client| Member {
+				client,
+				available: AtomicBool::new(true),
+			})
+			.collect();
+
+		Pool {
+			config,
+			secret_key,
+			members,
+			idle: Mutex::new((0..size).collect()),
+			permits: Semaphore::new(size),
+		}
+	}
+
+	/// Some connection still answers
+	pub fn is_available(&self) -> bool {
+		self.members.iter().any(Member::is_available)
+	}
+
+	pub fn timeout(&self) -> Duration {
+		Duration::from_secs(self.config.retry.timeout)
+	}
+
+	/// Waits for a free connection, no longer than the query timeout. Those still answering
+	/// are handed out first
+	pub async fn checkout(&self) -> surrealdb::Result<Checkout<'_>> {
+		let permit = match timeout(self.timeout(), self.permits.acquire()).await {
+			Ok(Ok(permit)) => permit,
+			_ => return Err(Api::Ws("no database connection free".to_owned()).into()),
+		};
+
+		let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+		let position =
+			idle.iter().position(|index| self.members[*index].is_available()).unwrap_or(0);
+		let index = idle.remove(position).expect("one idle connection for every permit");
+
+		Ok(Checkout {
+			pool: self,
+			index,
+			_permit: permit,
+		})
+	}
+
+	/// Applies the pending migrations through one of the connections. Not timed, building
+	/// an index on a large table takes its while
+	pub async fn migrate(&self) -> surrealdb::Result<usize> {
+		let checkout = self.checkout().await?;
+		migrations::apply(&checkout.member().client, self.config.migrations.allow_changed).await
+	}
+
+	/// Looks at every connection, preparing again those that are back
+	pub async fn check(&self) {
+		for (index, member) in self.members.iter().enumerate() {
+			let healthy = timeout(self.timeout(), member.client.health().into_future())
+				.await
+				.is_ok_and(|health| health.is_ok());
+
+			match (member.is_available(), healthy) {
+				(true, false) => {
+					warn!(
+						address = link::address(&self.config),
+						connection = index,
+						"Database connection lost"
+					);
+					member.available.store(false, Ordering::Relaxed);
+				}
+				(false, true) => {
+					// the store may be back empty, as a memory one after a restart
+					let prepared =
+						db::prepare(&member.client, &self.config, self.secret_key.as_deref());
+
+					match timeout(self.timeout(), prepared).await {
+						Ok(Ok(())) => {
+							info!(
+								address = link::address(&self.config),
+								connection = index,
+								"Database connection restored"
+							);
+							member.available.store(true, Ordering::Relaxed);
+						}
+						Ok(Err(e)) => {
+							warn!(error = %e, "Error preparing the database connection")
+						}
+						Err(_) => warn!("Timed out preparing the database connection"),
+					}
+				}
+				_ => (),
+			}
+		}
+	}
+}
+
+impl Member {
+	pub fn is_available(&self) -> bool {
+		self.available.load(Ordering::Relaxed)
+	}
+}
+
+impl Checkout<'_> {
+	pub fn member(&self) -> &Member {
+		&self.pool.members[self.index]
+	}
+}
+
+impl Drop for Checkout<'_> {
+	fn drop(&mut self) {
+		let mut idle = self.pool.idle.lock().unwrap_or_else(PoisonError::into_inner);
+		idle.push_back(self.index);
+	}
+}
@@ -0,0 +1,81 @@
+use std::marker::PhantomData;
+
+use crate::app::providers::models::user::Role;
+use crate::app::providers::services::audit::request::{Actor, RequestMeta};
+
+use super::claims::Claims;
+
+/// Type level role, used as parameter of the role guards
+pub trait RoleMarker: Send + Sync + 'static {
+	const ROLE: Role;
+}
+
+macro_rules! role_markers {
+	($($marker:ident => $role:expr),* $(,)?) => {
+		$(
+			#[allow(dead_code)]
+			pub struct $marker;
+
+			impl RoleMarker for $marker {
+				const ROLE: Role = $role;
+			}
+		)*
+	};
+}
+
+role_markers! {
+	Robot => Role::Robot,
+	Admin => Role::Admin,
+	Coord => Role::Coord,
+	Thera => Role::Thera,
+	Parti => Role::Parti,
+	Guest => Role::Guest,
+}
+
+/// Succeeds only when the token role is exactly `R`
+pub struct RequireRole<R: RoleMarker> {
+	pub claims: Claims,
+	pub meta: RequestMeta,
+	role: PhantomData<R>,
+}
+
+/// Succeeds when the token role is `R` or above it in the hierarchy
+pub struct AtLeast<R: RoleMarker> {
+	pub claims: Claims,
+	pub meta: RequestMeta,
+	role: PhantomData<R>,
+}
+
+impl<R: RoleMarker> RequireRole<R> {
+	pub fn new(claims: Claims, meta: RequestMeta) -> Self {
+		RequireRole {
+			claims,
+			meta,
+			role: PhantomData,
+		}
+	}
+
+	pub fn actor(&self) -> Actor<'_> {
+		Actor {
+			claims: &self.claims,
+			meta: &self.meta,
+		}
+	}
+}
+
+impl<R: RoleMarker> AtLeast<R> {
+	pub fn new(claims: Claims, meta: RequestMeta) -> Self {
+		AtLeast {
+			claims,
+			meta,
+			role: PhantomData,
+		}
+	}
+
+	pub fn actor(&self) -> Actor<'_> {
+		Actor {
+			claims: &self.claims,
+			meta: &self.meta,
+		}
+	}
+}
@@ -0,0 +1,67 @@
+use surrealdb::sql::Thing;
+
+use super::db::DbAuth;
+
+/// Every token carries the id of the session opened at login, tokens of a revoked session
+/// are refused even before they expire. The session must be the one of `user`, the subject
+/// of the token
+pub async fn is_live(db: &DbAuth, sid: &str, user: &str) -> Result<bool, surrealdb::Error> {
+	let (sid, user): (Thing, Thing) = match (sid.parse(), user.parse()) {
+		(Ok(sid), Ok(user)) => (sid, user),
+		_ => return Ok(false),
+	};
+
+	let mut query = db
+		.query(
+			"sessions.is_live",
+			"RETURN (SELECT VALUE revoked = false FROM ONLY $b_sid WHERE user = $b_user);",
+		)
+		.bind(("b_sid", sid))
+		.bind(("b_user", user))
+		.await?;
+
+	let live: Option<bool> = query.take(query.num_statements() - 1)?;
+
+	Ok(live.unwrap_or(false))
+}
+
+/// Closes one session, false when it was already closed or never existed
+pub async fn revoke(db: &DbAuth, sid: &str) -> Result<bool, surrealdb::Error> {
+	let sid: Thing = match sid.parse() {
+		Ok(sid) => sid,
+		Err(_) => return Ok(false),
+	};
+
+	let mut query = db
+		.query(
+			"sessions.revoke",
+			r#"
+			RETURN (UPDATE $b_sid SET revoked = true, revoked_at = time::now(), reason = 'logout'
+				WHERE revoked = false)[0].id;
+			"#,
+		)
+		.bind(("b_sid", sid))
+		.await?;
+
+	let revoked: Option<Thing> = query.take(query.num_statements() - 1)?;
+
+	Ok(revoked.is_some())
+}
+
+/// Open sessions refreshed within `ttl` seconds, the others have no token left that works
+pub async fn count_live(db: &DbAuth, ttl: u64) -> Result<u64, surrealdb::Error> {
+	let mut query = db
+		.read(
+			"sessions.count_live",
+			r#"
+			RETURN count(SELECT id FROM sessions
+				WHERE revoked = false AND refreshed_at > time::now() - <duration> $b_ttl);
+			"#,
+		)
+		.bind(("b_ttl", format!("{ttl}s")))
+		.await?;
+
+	let live: Option<u64> = query.take(query.num_statements() - 1)?;
+
+	Ok(live.unwrap_or(0))
+}
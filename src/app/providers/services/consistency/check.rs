@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rocket::fairing::AdHoc;
+use rocket::serde::Serialize;
+use surrealdb::sql::Thing;
+use tracing::{error, warn};
+
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::services::auth::db::DbAuth;
+
+/// Users whose project has no active `join` edge to it
+const MISSING_JOIN: &str = r#"
+	SELECT id, project FROM users
+		WHERE project != NONE AND project NOTINSIDE ->join[WHERE pending != true].out
+"#;
+
+/// Users without a role in the center of their project
+const MISSING_ROLED: &str = r#"
+	SELECT id, project, project.center AS center FROM users
+		WHERE project.center != NONE AND project.center NOTINSIDE ->roled.out
+"#;
+
+/// The role `$user` was admitted to its project with, by its `join` or the invitation
+/// behind it
+const ADMITTED_ROLE: &str = r#"
+	(SELECT VALUE role ?? invitation.role FROM join
+		WHERE in = $user.id AND out = $user.project AND pending != true)[0]
+"#;
+
+/// Users that belong nowhere, there is nothing to rebuild them from
+const ORPHANS: &str = "SELECT id FROM users WHERE count(->roled) = 0 AND count(->join) = 0";
+
+/// Users missing the edges signup should have created
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Report {
+	pub checked_at: DateTime<Utc>,
+	pub missing_join: Vec<Cow<'static, str>>,
+	pub missing_roled: Vec<Cow<'static, str>>,
+	pub orphans: Vec<Cow<'static, str>>,
+	pub fixed: bool,
+	/// Left without their role by the fix, nothing tells which one they were admitted with
+	pub unresolved: Vec<Cow<'static, str>>,
+}
+
+impl Report {
+	pub fn is_clean(&self) -> bool {
+		self.missing_join.is_empty() && self.missing_roled.is_empty() && self.orphans.is_empty()
+	}
+}
+
+pub async fn report(db: &DbAuth) -> Result<Report, surrealdb::Error> {
+	let sql = format!(
+		r#"
+		RETURN SELECT VALUE id FROM ({MISSING_JOIN});
+		RETURN SELECT VALUE id FROM ({MISSING_ROLED});
+		RETURN SELECT VALUE id FROM ({ORPHANS});
+		"#
+	);
+
+	let mut query = db.query("consistency.report", sql).await?;
+
+	let orphans: Vec<Thing> = query.take(query.num_statements() - 1)?;
+	let missing_roled: Vec<Thing> = query.take(query.num_statements() - 1)?;
+	let missing_join: Vec<Thing> = query.take(query.num_statements() - 1)?;
+
+	Ok(Report {
+		checked_at: Utc::now(),
+		missing_join: ids(missing_join),
+		missing_roled: ids(missing_roled),
+		orphans: ids(orphans),
+		fixed: false,
+		unresolved: Vec::new(),
+	})
+}
+
+/// Relates the users found to their project and to its center, with the role they were
+/// admitted with. Those it cannot tell, and orphans, are left to an admin
+pub async fn fix(db: &DbAuth) -> Result<Report, surrealdb::Error> {
+	let mut report = report(db).await?;
+	if report.missing_join.is_empty() && report.missing_roled.is_empty() {
+		return Ok(report);
+	}
+
+	let sql = format!(
+		r#"
+		BEGIN TRANSACTION;
+
+		FOR $user IN ({MISSING_JOIN}) {{
+			DELETE join WHERE in = $user.id AND out = $user.project;
+			RELATE ($user.id)->join->($user.project);
+		}};
+
+		FOR $user IN ({MISSING_ROLED}) {{
+			LET $q_role = {ADMITTED_ROLE};
+			IF $q_role {{
+				RELATE ($user.id)->roled->($user.center) SET role = $q_role;
+			}};
+		}};
+
+		COMMIT TRANSACTION;
+
+		SELECT VALUE id FROM ({MISSING_ROLED});
+		"#
+	);
+
+	let mut query = db.query("consistency.fix", sql).await?.check()?;
+	let unresolved: Vec<Thing> = query.take(query.num_statements() - 1)?;
+
+	report.fixed = true;
+	report.unresolved = ids(unresolved);
+	Ok(report)
+}
+
+/// Runs the check every `consistency.interval` seconds once the server is up, fixing what it
+/// finds when `consistency.fix` is set
+pub fn job() -> AdHoc {
+	AdHoc::on_liftoff("Consistency check", |rocket| {
+		Box::pin(async move {
+			let config = match rocket.state::<Settings>() {
+				Some(settings) => settings.consistency.clone(),
+				None => return,
+			};
+			if config.interval == 0 {
+				return;
+			}
+
+			let db = match rocket.state::<DbAuth>() {
+				Some(db) => db.clone(),
+				None => return,
+			};
+
+			rocket::tokio::spawn(async move {
+				let mut interval =
+					rocket::tokio::time::interval(Duration::from_secs(config.interval));
+
+				loop {
+					interval.tick().await;
+
+					let result = if config.fix {
+						fix(&db).await
+					} else {
+						report(&db).await
+					};
+					match result {
+						Ok(report) if report.is_clean() => {}
+						Ok(report) => warn!(?report, "Inconsistent users found"),
+						Err(e) => error!(error = %e, "Error checking consistency"),
+					}
+				}
+			});
+		})
+	})
+}
+
+fn ids(things: Vec<Thing>) -> Vec<Cow<'static, str>> {
+	things.into_iter().map(|thing| thing.to_string().into()).collect()
+}
@@ -0,0 +1,160 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use rocket::serde::json::Value;
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::services::schema::migrations;
+
+/// A check taking longer than this counts as failed, the orchestrator should not wait on us
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Tables the handlers cannot do without, defined by the first migration
+const REQUIRED_TABLES: [&str; 5] = ["users", "projects", "centers", "roled", "join"];
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Check {
+	pub name: &'static str,
+	pub ok: bool,
+	pub latency_ms: f64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub detail: Option<Cow<'static, str>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Readiness {
+	pub ready: bool,
+	pub checks: Vec<Check>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct DbInfo {
+	tables: BTreeMap<String, Value>,
+	scopes: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct ScopeInfo {
+	tokens: BTreeMap<String, Value>,
+}
+
+/// Runs every check, one after the other so their latencies are not mixed
+pub async fn readiness(db: &DbAuth) -> Readiness {
+	let mut checks = vec![
+		check("database", database(db)).await,
+		check("signin", signin(db)).await,
+		check("schema", schema(db)).await,
+		check("signing_key", signing_key(db)).await,
+	];
+
+	if db.has_read_endpoint() {
+		checks.push(check("database_read", database_read(db)).await);
+	}
+
+	Readiness {
+		ready: checks.iter().all(|check| check.ok),
+		checks,
+	}
+}
+
+async fn check<F>(name: &'static str, probe: F) -> Check
+where
+	F: Future<Output = Result<(), Cow<'static, str>>>,
+{
+	let started = Instant::now();
+	let result = match rocket::tokio::time::timeout(TIMEOUT, probe).await {
+		Ok(result) => result,
+		Err(_) => Err("timed out".into()),
+	};
+
+	Check {
+		name,
+		ok: result.is_ok(),
+		latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+		detail: result.err(),
+	}
+}
+
+/// The connection answers
+async fn database(db: &DbAuth) -> Result<(), Cow<'static, str>> {
+	db.query("health.database", "RETURN true;")
+		.await
+		.map_err(reason)?
+		.check()
+		.map_err(reason)?;
+
+	Ok(())
+}
+
+/// The read endpoint answers, signed in
+async fn database_read(db: &DbAuth) -> Result<(), Cow<'static, str>> {
+	db.read("health.database_read", "INFO FOR DB;")
+		.await
+		.map_err(reason)?
+		.check()
+		.map_err(reason)?;
+
+	Ok(())
+}
+
+/// The session still has its rights on the database, anonymous ones are refused. Asked of
+/// the database, a user defined there sees nothing above it
+async fn signin(db: &DbAuth) -> Result<(), Cow<'static, str>> {
+	db.query("health.signin", "INFO FOR DB;").await.map_err(reason)?.check().map_err(reason)?;
+
+	Ok(())
+}
+
+/// The store tables and the `user` scope are defined, and no migration is left to apply
+async fn schema(db: &DbAuth) -> Result<(), Cow<'static, str>> {
+	let mut query = db
+		.query("health.schema", "INFO FOR DB; SELECT VALUE version FROM migrations;")
+		.await
+		.map_err(reason)?;
+	let info: Option<DbInfo> = query.take(0).map_err(reason)?;
+	let info = info.ok_or("no database info")?;
+	let applied: Vec<u32> = query.take(1).map_err(reason)?;
+
+	let mut missing: Vec<String> = REQUIRED_TABLES
+		.into_iter()
+		.filter(|table| !info.tables.contains_key(*table))
+		.map(str::to_owned)
+		.collect();
+
+	if !info.scopes.contains_key("user") {
+		missing.push("scope user".to_owned());
+	}
+
+	for version in migrations::pending(&applied) {
+		missing.push(format!("migration {version}"));
+	}
+
+	if !missing.is_empty() {
+		return Err(format!("missing {}", missing.join(", ")).into());
+	}
+
+	Ok(())
+}
+
+/// The database accepts the tokens we sign, the key itself was checked at startup
+async fn signing_key(db: &DbAuth) -> Result<(), Cow<'static, str>> {
+	let mut query =
+		db.query("health.signing_key", "INFO FOR SCOPE user;").await.map_err(reason)?;
+	let info: Option<ScopeInfo> = query.take(0).map_err(reason)?;
+
+	match info {
+		Some(info) if info.tokens.contains_key("user_scope") => Ok(()),
+		_ => Err("token user_scope is not defined".into()),
+	}
+}
+
+fn reason(error: surrealdb::Error) -> Cow<'static, str> {
+	error.to_string().into()
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::Serialize;
+
+/// What is running, filled in at build time by `build.rs`
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Version {
+	pub name: &'static str,
+	pub version: &'static str,
+	pub git_sha: &'static str,
+	pub built_at: Option<DateTime<Utc>>,
+	pub rustc: &'static str,
+	pub profile: &'static str,
+	pub target: &'static str,
+}
+
+pub fn current() -> Version {
+	let built_at =
+		env!("BUILD_TIMESTAMP").parse().ok().and_then(|secs| DateTime::from_timestamp(secs, 0));
+
+	Version {
+		name: env!("CARGO_PKG_NAME"),
+		version: env!("CARGO_PKG_VERSION"),
+		git_sha: env!("BUILD_GIT_SHA"),
+		built_at,
+		rustc: env!("BUILD_RUSTC"),
+		profile: env!("BUILD_PROFILE"),
+		target: env!("BUILD_TARGET"),
+	}
+}
@@ -0,0 +1,68 @@
+use std::borrow::Cow;
+use std::time::Instant;
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Data, Request, Response};
+use tracing::{error, info, warn};
+
+use crate::app::providers::services::audit::request::{RequestMeta, REQUEST_ID_HEADER};
+use crate::app::providers::services::metrics::registry::metrics;
+
+/// User of the request, set by the token guard once the token is accepted
+pub struct Subject(pub Option<Cow<'static, str>>);
+
+struct Started(Instant);
+
+/// One line per request with its id, route pattern, user, status and latency. The raw uri
+/// is left out so query strings and headers, tokens included, never reach the logs. The
+/// same numbers feed the request metrics
+pub struct RequestLog;
+
+#[rocket::async_trait]
+impl Fairing for RequestLog {
+	fn info(&self) -> Info {
+		Info {
+			name: "Request log",
+			kind: Kind::Request | Kind::Response,
+		}
+	}
+
+	async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+		request.local_cache(|| Started(Instant::now()));
+		// fixes the id before any guard asks for it
+		RequestMeta::from_request(request);
+	}
+
+	async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+		let meta = RequestMeta::from_request(request);
+		response.set_raw_header(REQUEST_ID_HEADER, meta.request_id.to_string());
+
+		let latency = request.local_cache(|| Started(Instant::now())).0.elapsed();
+		let route = request.route().map(|route| route.uri.to_string());
+		let user = request.local_cache(|| Subject(None)).0.as_deref();
+		let status = response.status().code;
+
+		let method = request.method().as_str();
+		metrics().request(method, route.as_deref().unwrap_or("-"), status, latency);
+
+		macro_rules! log {
+			($level:ident) => {
+				$level!(
+					request_id = %meta.request_id,
+					method = %request.method(),
+					route = route.as_deref().unwrap_or("-"),
+					status,
+					latency_ms = latency.as_secs_f64() * 1000.0,
+					user,
+					"request"
+				)
+			};
+		}
+
+		match status {
+			500.. => log!(error),
+			400..=499 => log!(warn),
+			_ => log!(info),
+		}
+	}
+}
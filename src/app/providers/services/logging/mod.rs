@@ -0,0 +1,2 @@
+pub mod fairing;
+pub mod subscriber;
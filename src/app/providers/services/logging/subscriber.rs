@@ -0,0 +1,21 @@
+use tracing_subscriber::EnvFilter;
+
+use crate::app::providers::config::getter::{LogFormat, LoggingConfig};
+
+/// Installs the global subscriber, must run before anything logs
+pub fn init(config: &LoggingConfig) {
+	let filter = EnvFilter::try_from_default_env()
+		.or_else(|_| EnvFilter::try_new(config.level.as_ref()))
+		.unwrap_or_else(|_| EnvFilter::new("info"));
+
+	let builder = tracing_subscriber::fmt().with_env_filter(filter).with_target(false);
+
+	let result = match config.format {
+		LogFormat::Json => builder.json().flatten_event(true).try_init(),
+		LogFormat::Pretty => builder.try_init(),
+	};
+
+	if let Err(e) = result {
+		eprintln!("Logging was already set up: {}", e);
+	}
+}
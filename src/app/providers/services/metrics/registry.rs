@@ -0,0 +1,136 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use prometheus::{
+	Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use rocket::http::Status;
+use tracing::error;
+
+/// Every metric of the service, registered once and shared by the whole process
+pub struct Metrics {
+	registry: Registry,
+	pub requests: IntCounterVec,
+	pub request_duration: HistogramVec,
+	pub logins: IntCounterVec,
+	pub tokens: IntCounterVec,
+	pub sessions: IntGauge,
+	pub query_duration: HistogramVec,
+	pub query_errors: IntCounterVec,
+}
+
+pub fn metrics() -> &'static Metrics {
+	static METRICS: OnceLock<Metrics> = OnceLock::new();
+	METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+	fn new() -> Self {
+		let requests = IntCounterVec::new(
+			Opts::new("http_requests_total", "Requests answered, by route and status"),
+			&["method", "route", "status"],
+		)
+		.expect("valid metric");
+
+		let request_duration = HistogramVec::new(
+			HistogramOpts::new("http_request_duration_seconds", "Time to answer a request"),
+			&["method", "route", "status"],
+		)
+		.expect("valid metric");
+
+		let logins = IntCounterVec::new(
+			Opts::new("auth_logins_total", "Login attempts, by outcome and reason"),
+			&["outcome", "reason"],
+		)
+		.expect("valid metric");
+
+		let tokens = IntCounterVec::new(
+			Opts::new("auth_tokens_issued_total", "Tokens signed, global or project"),
+			&["kind"],
+		)
+		.expect("valid metric");
+
+		let sessions = IntGauge::new(
+			"auth_active_sessions",
+			"Sessions open and refreshed within the token lifetime",
+		)
+		.expect("valid metric");
+
+		let query_duration = HistogramVec::new(
+			HistogramOpts::new("db_query_duration_seconds", "Time spent in SurrealDB queries")
+				.buckets(vec![
+					0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+				]),
+			&["query"],
+		)
+		.expect("valid metric");
+
+		let query_errors = IntCounterVec::new(
+			Opts::new("db_query_errors_total", "SurrealDB queries that failed to run"),
+			&["query"],
+		)
+		.expect("valid metric");
+
+		let registry = Registry::new();
+		registry.register(Box::new(requests.clone())).expect("unique metric");
+		registry.register(Box::new(request_duration.clone())).expect("unique metric");
+		registry.register(Box::new(logins.clone())).expect("unique metric");
+		registry.register(Box::new(tokens.clone())).expect("unique metric");
+		registry.register(Box::new(sessions.clone())).expect("unique metric");
+		registry.register(Box::new(query_duration.clone())).expect("unique metric");
+		registry.register(Box::new(query_errors.clone())).expect("unique metric");
+
+		Metrics {
+			registry,
+			requests,
+			request_duration,
+			logins,
+			tokens,
+			sessions,
+			query_duration,
+			query_errors,
+		}
+	}
+
+	pub fn request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+		let status = status.to_string();
+		let labels = [method, route, status.as_str()];
+
+		self.requests.with_label_values(&labels).inc();
+		self.request_duration.with_label_values(&labels).observe(elapsed.as_secs_f64());
+	}
+
+	/// Reason follows the status sent back, wrong and disabled credentials look the same
+	pub fn login<T>(&self, result: &Result<T, Status>) {
+		let (outcome, reason) = match result.as_ref().map_err(|status| status.code) {
+			Ok(_) => ("success", "ok"),
+			Err(401) => ("failure", "bad_credentials"),
+			Err(423) => ("failure", "standby"),
+			Err(500..) => ("failure", "error"),
+			Err(_) => ("failure", "other"),
+		};
+
+		self.logins.with_label_values(&[outcome, reason]).inc();
+	}
+
+	pub fn token(&self, kind: &str) {
+		self.tokens.with_label_values(&[kind]).inc();
+	}
+
+	pub fn query(&self, name: &str, elapsed: Duration, failed: bool) {
+		self.query_duration.with_label_values(&[name]).observe(elapsed.as_secs_f64());
+		if failed {
+			self.query_errors.with_label_values(&[name]).inc();
+		}
+	}
+
+	/// Prometheus text format
+	pub fn render(&self) -> String {
+		let mut buffer = Vec::new();
+		if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+			error!(error = %e, "Error encoding metrics");
+		}
+
+		String::from_utf8(buffer).unwrap_or_default()
+	}
+}
@@ -1 +1,8 @@
+pub mod audit;
 pub mod auth;
+pub mod consistency;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod schema;
+pub mod webhooks;
@@ -0,0 +1,141 @@
+use rocket::serde::Deserialize;
+use sha2::{Digest, Sha256};
+use surrealdb::engine::any::Any;
+use surrealdb::error::Api;
+use surrealdb::Surreal;
+use tracing::{info, warn};
+
+/// One step of the schema, applied once and in order. An applied migration is never
+/// edited, changes go in a new one
+pub struct Migration {
+	pub version: u32,
+	pub name: &'static str,
+	pub sql: &'static str,
+}
+
+macro_rules! migration {
+	($version:literal, $name:literal, $file:literal) => {
+		Migration {
+			version: $version,
+			name: $name,
+			sql: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/", $file)),
+		}
+	};
+}
+
+pub const MIGRATIONS: [Migration; 4] = [
+	migration!(1, "store", "0001_store.surql"),
+	migration!(2, "access", "0002_access.surql"),
+	migration!(3, "audit", "0003_audit.surql"),
+	migration!(4, "admission", "0004_admission.surql"),
+];
+
+/// Where the applied migrations are recorded
+const BOOTSTRAP: &str = r#"
+	DEFINE TABLE migrations SCHEMAFULL;
+	DEFINE FIELD version ON migrations TYPE int;
+	DEFINE FIELD name ON migrations TYPE string;
+	DEFINE FIELD checksum ON migrations TYPE string;
+	DEFINE FIELD applied_at ON migrations TYPE datetime;
+"#;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Applied {
+	version: u32,
+	checksum: String,
+}
+
+impl Migration {
+	pub fn checksum(&self) -> String {
+		hex::encode(Sha256::digest(self.sql.as_bytes()))
+	}
+}
+
+/// Applies the pending migrations in order, each one in a transaction with its record so a
+/// failed one leaves nothing behind. Returns how many were applied. An applied migration
+/// whose file changed since fails it, unless `allow_changed`
+pub async fn apply(db: &Surreal<Any>, allow_changed: bool) -> surrealdb::Result<usize> {
+	db.query(BOOTSTRAP).await?.check()?;
+
+	let applied: Vec<Applied> = db
+		.query("SELECT version, checksum FROM migrations ORDER BY version;")
+		.await?
+		.take(0)?;
+
+	let mut count = 0;
+	for migration in MIGRATIONS.iter() {
+		let checksum = migration.checksum();
+
+		match applied.iter().find(|applied| applied.version == migration.version) {
+			Some(applied) if applied.checksum != checksum => {
+				if !allow_changed {
+					return Err(Api::Query(format!(
+						"migration {} ({}) changed since it was applied",
+						migration.version, migration.name
+					))
+					.into());
+				}
+
+				warn!(
+					version = migration.version,
+					name = migration.name,
+					"Migration changed since it was applied"
+				);
+				continue;
+			}
+			Some(_) => continue,
+			None => (),
+		}
+
+		let sql = format!(
+			r#"
+			BEGIN TRANSACTION;
+			{}
+			CREATE type::thing('migrations', $b_version) CONTENT {{
+				version: $b_version,
+				name: $b_name,
+				checksum: $b_checksum,
+				applied_at: time::now(),
+			}} RETURN NONE;
+			COMMIT TRANSACTION;
+			"#,
+			migration.sql
+		);
+
+		db.query(sql)
+			.bind(("b_version", migration.version))
+			.bind(("b_name", migration.name))
+			.bind(("b_checksum", checksum))
+			.await?
+			.check()?;
+
+		info!(version = migration.version, name = migration.name, "Migration applied");
+		count += 1;
+	}
+
+	if let Some(newest) = applied.iter().map(|applied| applied.version).max() {
+		if newest > latest() {
+			warn!(
+				newest,
+				latest = latest(),
+				"The database has migrations this build does not know"
+			);
+		}
+	}
+
+	Ok(count)
+}
+
+pub fn latest() -> u32 {
+	MIGRATIONS.iter().map(|migration| migration.version).max().unwrap_or(0)
+}
+
+/// Versions not recorded in the database yet
+pub fn pending(applied: &[u32]) -> Vec<u32> {
+	MIGRATIONS
+		.iter()
+		.map(|migration| migration.version)
+		.filter(|version| !applied.contains(version))
+		.collect()
+}
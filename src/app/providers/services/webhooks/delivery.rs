@@ -0,0 +1,290 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use chrono::Utc;
+use rocket::fairing::AdHoc;
+use rocket::serde::json::{self, json, Value};
+use rocket::serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+use tracing::{error, warn};
+
+use crate::app::providers::config::getter::WebhooksConfig;
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::services::audit::trail::AuditEntry;
+use crate::app::providers::services::auth::db::DbAuth;
+
+use super::signature::{
+	self, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+
+/// Deliveries taken on every round
+const BATCH: u32 = 50;
+/// Retries never wait longer than a day
+const MAX_BACKOFF: u64 = 24 * 3600;
+
+/// Body sent to the subscribers, ip and user agent stay in the audit log
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Event {
+	pub event: Cow<'static, str>,
+	pub at: chrono::DateTime<Utc>,
+	pub user: Option<Cow<'static, str>>,
+	pub target: Option<Cow<'static, str>>,
+	pub project: Option<Cow<'static, str>>,
+	pub detail: Value,
+}
+
+impl From<&AuditEntry> for Event {
+	fn from(entry: &AuditEntry) -> Self {
+		Event {
+			event: entry.action.clone(),
+			at: entry.at.0,
+			user: entry.actor.as_ref().map(|user| user.to_string().into()),
+			target: entry.target.as_ref().map(|target| target.to_string().into()),
+			project: entry.project.as_ref().map(|project| project.to_string().into()),
+			detail: entry.detail.clone(),
+		}
+	}
+}
+
+/// Queues the entry for every active subscription to its action, or to `*`
+pub async fn enqueue(db: &DbAuth, entry: &AuditEntry) -> Result<(), surrealdb::Error> {
+	let body = json::to_string(&Event::from(entry)).unwrap_or_default();
+
+	db.query(
+		"deliveries.enqueue",
+		r#"
+			FOR $hook IN (SELECT VALUE id FROM webhooks
+				WHERE active = true AND (events CONTAINS $b_event OR events CONTAINS '*')) {
+				CREATE webhook_deliveries CONTENT {
+					webhook: $hook,
+					event: $b_event,
+					body: $b_body,
+					attempts: 0,
+					next_at: time::now(),
+					created_at: time::now(),
+				} RETURN NONE;
+			};
+			"#,
+	)
+	.bind(("b_event", &entry.action))
+	.bind(("b_body", body))
+	.await?
+	.check()?;
+
+	Ok(())
+}
+
+/// Queues a `ping` for one subscription, so receivers can be checked by hand
+pub async fn ping(db: &DbAuth, webhook: &Thing) -> Result<(), surrealdb::Error> {
+	let body = json::to_string(&json!({
+		"event": "ping",
+		"at": Utc::now(),
+		"webhook": webhook.to_string(),
+	}))
+	.unwrap_or_default();
+
+	db.query(
+		"deliveries.ping",
+		r#"
+			CREATE webhook_deliveries CONTENT {
+				webhook: $b_webhook,
+				event: 'ping',
+				body: $b_body,
+				attempts: 0,
+				next_at: time::now(),
+				created_at: time::now(),
+			} RETURN NONE;
+			"#,
+	)
+	.bind(("b_webhook", webhook))
+	.bind(("b_body", body))
+	.await?
+	.check()?;
+
+	Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Due {
+	id: Thing,
+	event: Cow<'static, str>,
+	body: Cow<'static, str>,
+	attempts: u32,
+	/// Missing once the subscription is removed
+	url: Option<Cow<'static, str>>,
+	secret: Option<Cow<'static, str>>,
+}
+
+/// Sends every due delivery once, returns how many went through
+pub async fn round(
+	db: &DbAuth,
+	client: &reqwest::Client,
+	config: &WebhooksConfig,
+) -> Result<usize, surrealdb::Error> {
+	let mut query = db
+		.query(
+			"deliveries.due",
+			r#"
+			RETURN SELECT id, event, body, attempts, next_at, webhook.url AS url,
+				webhook.secret AS secret
+				FROM webhook_deliveries WHERE next_at <= time::now()
+				ORDER BY next_at LIMIT $b_batch;
+			"#,
+		)
+		.bind(("b_batch", BATCH))
+		.await?;
+
+	let due: Vec<Due> = query.take(query.num_statements() - 1)?;
+
+	let mut delivered = 0;
+	for delivery in due {
+		let result = match (&delivery.url, &delivery.secret) {
+			(Some(url), Some(secret)) => send(client, url, secret, &delivery).await,
+			_ => Err("webhook removed".to_owned()),
+		};
+
+		match result {
+			Ok(()) => {
+				db.query("deliveries.delete", "DELETE $b_id;")
+					.bind(("b_id", &delivery.id))
+					.await?
+					.check()?;
+				delivered += 1;
+			}
+			Err(error) => fail(db, config, &delivery, error).await?,
+		}
+	}
+
+	Ok(delivered)
+}
+
+async fn send(
+	client: &reqwest::Client,
+	url: &str,
+	secret: &str,
+	delivery: &Due,
+) -> Result<(), String> {
+	let timestamp = Utc::now().timestamp();
+
+	let response = client
+		.post(url)
+		.header("Content-Type", "application/json")
+		.header(EVENT_HEADER, delivery.event.as_ref())
+		.header(DELIVERY_HEADER, delivery.id.to_string())
+		.header(TIMESTAMP_HEADER, timestamp)
+		.header(SIGNATURE_HEADER, signature::sign(secret, timestamp, &delivery.body))
+		.body(delivery.body.to_string())
+		.send()
+		.await
+		.map_err(|e| e.to_string())?;
+
+	if response.status().is_success() {
+		Ok(())
+	} else {
+		Err(format!("receiver answered {}", response.status()))
+	}
+}
+
+/// Schedules the next attempt, or moves the delivery to `webhook_dead` when the receiver
+/// is gone or the attempts are spent
+async fn fail(
+	db: &DbAuth,
+	config: &WebhooksConfig,
+	delivery: &Due,
+	error: String,
+) -> Result<(), surrealdb::Error> {
+	let attempts = delivery.attempts + 1;
+
+	if delivery.url.is_none() || attempts >= config.max_attempts {
+		db.query(
+			"deliveries.bury",
+			r#"
+				BEGIN TRANSACTION;
+
+				LET $q_delivery = (SELECT * FROM ONLY $b_id);
+				CREATE webhook_dead CONTENT {
+					webhook: $q_delivery.webhook,
+					event: $q_delivery.event,
+					body: $q_delivery.body,
+					attempts: $b_attempts,
+					error: $b_error,
+					created_at: $q_delivery.created_at,
+					failed_at: time::now(),
+				} RETURN NONE;
+				DELETE $b_id;
+
+				COMMIT TRANSACTION;
+				"#,
+		)
+		.bind(("b_id", &delivery.id))
+		.bind(("b_attempts", attempts))
+		.bind(("b_error", &error))
+		.await?
+		.check()?;
+
+		warn!(delivery = %delivery.id, attempts, error, "Webhook delivery moved to the dead letters");
+		return Ok(());
+	}
+
+	let wait =
+		config.backoff.saturating_mul(2u64.saturating_pow(attempts - 1)).min(MAX_BACKOFF);
+	let next_at = Utc::now() + chrono::Duration::seconds(wait as i64);
+
+	db.query(
+		"deliveries.retry_later",
+		"UPDATE $b_id SET attempts = $b_attempts, next_at = $b_next_at, error = $b_error;",
+	)
+	.bind(("b_id", &delivery.id))
+	.bind(("b_attempts", attempts))
+	.bind(("b_next_at", Datetime::from(next_at)))
+	.bind(("b_error", &error))
+	.await?
+	.check()?;
+
+	Ok(())
+}
+
+pub fn job() -> AdHoc {
+	AdHoc::on_liftoff("Webhook deliveries", |rocket| {
+		Box::pin(async move {
+			let config = match rocket.state::<Settings>() {
+				Some(settings) => settings.webhooks.clone(),
+				None => return,
+			};
+			if config.poll == 0 {
+				return;
+			}
+
+			let db = match rocket.state::<DbAuth>() {
+				Some(db) => db.clone(),
+				None => return,
+			};
+
+			let client = match reqwest::Client::builder()
+				.timeout(Duration::from_secs(config.timeout))
+				.build()
+			{
+				Ok(client) => client,
+				Err(e) => {
+					error!(error = %e, "Error building webhook client");
+					return;
+				}
+			};
+
+			rocket::tokio::spawn(async move {
+				let mut interval =
+					rocket::tokio::time::interval(Duration::from_secs(config.poll));
+
+				loop {
+					interval.tick().await;
+
+					if let Err(e) = round(&db, &client, &config).await {
+						error!(error = %e, "Error delivering webhooks");
+					}
+				}
+			});
+		})
+	})
+}
@@ -0,0 +1,19 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+/// `sha256=` followed by the hex HMAC of `{timestamp}.{body}` keyed with the subscription
+/// secret, receivers compute the same and compare it in constant time
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+	let mut mac =
+		Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+	mac.update(timestamp.to_string().as_bytes());
+	mac.update(b".");
+	mac.update(body.as_bytes());
+
+	format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -1,26 +1,122 @@
+use tracing::{error, info};
+
 use crate::app::modules::routing as modules_routing;
 
 use crate::app::providers::config::cors;
-use crate::app::providers::services::auth::db::DbAuth;
+use crate::app::providers::config::settings::Settings;
+use crate::app::providers::services::audit::trail as audit;
+use crate::app::providers::services::auth::catchers;
+use crate::app::providers::services::auth::db::{self, DbAuth};
+use crate::app::providers::services::auth::link::Unavailable;
+use crate::app::providers::services::consistency::check as consistency;
+use crate::app::providers::services::logging::fairing::RequestLog;
+use crate::app::providers::services::logging::subscriber;
+use crate::app::providers::services::schema::migrations;
+use crate::app::providers::services::webhooks::delivery as webhooks;
 
 #[launch]
 pub async fn rocket() -> _ {
-	rocket::build()
+	let (figment, settings) = Settings::load();
+	subscriber::init(&settings.logging);
+
+	let db = DbAuth::new(&settings).await;
+
+	rocket::custom(figment)
+		.attach(Unavailable)
+		.attach(RequestLog)
 		.attach(cors::Cors)
 		.attach(system::router())
 		.attach(modules_routing::router())
-		.manage(DbAuth::new().await)
+		.attach(db::watch())
+		.attach(consistency::job())
+		.attach(audit::job())
+		.attach(webhooks::job())
+		.register("/", catchers::catchers())
+		.manage(settings)
+		.manage(db)
+}
+
+/// `q-api-auth migrate`, brings the schema up to date and exits. For deployments that run
+/// the migrations as a step of their own, with `migrations.auto` off
+pub fn migrate() {
+	rocket::execute(async {
+		let (_, settings) = Settings::load();
+		subscriber::init(&settings.logging);
+
+		match DbAuth::new(&settings).await.migrate().await {
+			Ok(applied) => {
+				info!(applied, version = migrations::latest(), "Database schema up to date")
+			}
+			Err(e) => {
+				error!(error = %e, "Error applying the migrations");
+				std::process::exit(1);
+			}
+		}
+	})
 }
 
 mod system {
+	use rocket::http::{ContentType, Status};
+	use rocket::serde::json::{json, Json, Value};
+	use rocket::State;
+	use tracing::{error, warn};
+
+	use crate::app::providers::config::settings::Settings;
+	use crate::app::providers::services::auth::db::DbAuth;
+	use crate::app::providers::services::auth::session;
+	use crate::app::providers::services::health::probes::{self, Readiness};
+	use crate::app::providers::services::health::version::{self as build, Version};
+	use crate::app::providers::services::metrics::registry::metrics as registry;
+
 	pub fn router() -> rocket::fairing::AdHoc {
 		rocket::fairing::AdHoc::on_ignite("System Routes", |rocket| async {
-			rocket.mount("/", routes![health])
+			rocket.mount("/", routes![health, live, ready, version, metrics])
 		})
 	}
 
+	/// Prometheus scrape, the open sessions are counted on every scrape
+	#[get("/metrics")]
+	async fn metrics(db: &State<DbAuth>, settings: &State<Settings>) -> (ContentType, String) {
+		match session::count_live(db, settings.token_ttl).await {
+			Ok(live) => registry().sessions.set(live as i64),
+			Err(e) => error!(error = %e, "Error counting sessions"),
+		}
+
+		(
+			ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+			registry().render(),
+		)
+	}
+
 	#[get("/health")]
 	fn health() -> &'static str {
 		"OK"
 	}
+
+	/// The process answers, nothing else is looked at so a slow database never gets it
+	/// restarted
+	#[get("/health/live")]
+	fn live() -> Json<Value> {
+		Json(json!({ "live": true }))
+	}
+
+	/// Ready to take traffic, 503 with the failed checks otherwise
+	#[get("/health/ready")]
+	async fn ready(db: &State<DbAuth>) -> (Status, Json<Readiness>) {
+		let readiness = probes::readiness(db).await;
+		if readiness.ready {
+			return (Status::Ok, Json(readiness));
+		}
+
+		for check in readiness.checks.iter().filter(|check| !check.ok) {
+			warn!(check = check.name, detail = ?check.detail, "Readiness check failed");
+		}
+
+		(Status::ServiceUnavailable, Json(readiness))
+	}
+
+	#[get("/version")]
+	fn version() -> Json<Version> {
+		Json(build::current())
+	}
 }
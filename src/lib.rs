@@ -0,0 +1,5 @@
+pub mod app;
+
+#[macro_use]
+extern crate rocket;
+extern crate surrealdb;
@@ -1,9 +1,8 @@
-mod app;
-
-#[macro_use]
-extern crate rocket;
-extern crate surrealdb;
+use q_api_auth::app;
 
 fn main() {
-	app::server::main();
+	match std::env::args().nth(1).as_deref() {
+		Some("migrate") => app::server::migrate(),
+		_ => app::server::main(),
+	}
 }
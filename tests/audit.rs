@@ -0,0 +1,72 @@
+mod common;
+
+use chrono::{Duration, SecondsFormat, Utc};
+use rocket::http::Status;
+use rocket::serde::json::{json, Value};
+
+use common::{bearer, body, client, get, login, post, token};
+
+#[rocket::async_test]
+async fn signup_records_the_invitation_not_its_code() {
+	let client = client().await;
+
+	let credentials =
+		json!({ "username": "carol", "password": "carol-password", "code": "WELCOME" });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let admin = token(&client, "admin", "admin-password").await;
+	let response = get(&client, "/admin/audit?action=auth.signup", &admin).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let page = body(response).await;
+	let entry = &page["items"][0];
+	assert_eq!(entry["detail"]["invitation"], "invitations:welcome");
+	assert!(!entry.to_string().contains("WELCOME"));
+}
+
+#[rocket::async_test]
+async fn audit_is_filtered() {
+	let client = client().await;
+
+	assert_eq!(login(&client, "alice", "wrong").await.status(), Status::Unauthorized);
+	let alice = token(&client, "alice", "alice-password").await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response =
+		client.post("/admin/users/sleepy/disable").header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let actions = |page: &Value| -> Vec<String> {
+		let items = page["items"].as_array().expect("audit entries");
+		items.iter().map(|e| e["action"].as_str().unwrap().to_owned()).collect()
+	};
+
+	let page = body(get(&client, "/admin/audit?action=auth.", &admin).await).await;
+	assert_eq!(actions(&page), ["auth.login", "auth.login", "auth.login"]);
+
+	let page = body(get(&client, "/admin/audit?outcome=failure", &admin).await).await;
+	assert_eq!(page["total"], 1);
+	assert_eq!(page["items"][0]["detail"]["username"], "alice");
+
+	let page = body(get(&client, "/admin/audit?user=users:sleepy", &admin).await).await;
+	assert_eq!(actions(&page), ["user.disable"]);
+	assert_eq!(page["items"][0]["actor"], "users:admin");
+
+	let page = body(get(&client, "/admin/audit?per_page=1&page=2", &admin).await).await;
+	assert_eq!(actions(&page), ["auth.login"]);
+	assert_eq!(page["total"], 4);
+
+	// every entry is before tomorrow
+	let tomorrow = (Utc::now() + Duration::days(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+	let uri = format!("/admin/audit?from={tomorrow}");
+	assert_eq!(body(get(&client, &uri, &admin).await).await["total"], 0);
+	let uri = format!("/admin/audit?to={tomorrow}");
+	assert_eq!(body(get(&client, &uri, &admin).await).await["total"], 4);
+
+	for uri in ["/admin/audit?outcome=maybe", "/admin/audit?from=yesterday"] {
+		assert_eq!(get(&client, uri, &admin).await.status(), Status::BadRequest);
+	}
+
+	assert_eq!(get(&client, "/admin/audit", &alice).await.status(), Status::Forbidden);
+}
@@ -0,0 +1,280 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::json;
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{bearer, body, claims, client, login, post, sign, token, SECRET_KEY};
+
+#[rocket::async_test]
+async fn login_issues_tokens() {
+	let client = client().await;
+
+	let response = login(&client, "alice", "alice-password").await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	assert_eq!(user["id"], "users:alice");
+	assert_eq!(user["username"], "alice");
+	assert_eq!(user["role"], "parti");
+	assert_eq!(user["center"], "centers:north");
+	assert_eq!(user["project"]["id"], "projects:open");
+	assert_eq!(user["state"], "active");
+	assert!(user["g_token"].as_str().is_some_and(|token| !token.is_empty()));
+	assert!(user["p_token"].as_str().is_some_and(|token| !token.is_empty()));
+	assert!(user.get("password").is_none());
+}
+
+#[rocket::async_test]
+async fn login_with_wrong_password_is_unauthorized() {
+	let client = client().await;
+
+	let response = login(&client, "alice", "not-her-password").await;
+	assert_eq!(response.status(), Status::Unauthorized);
+
+	let response = login(&client, "nobody", "alice-password").await;
+	assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn login_on_standby_is_locked() {
+	let client = client().await;
+
+	let response = login(&client, "sleepy", "sleepy-password").await;
+	assert_eq!(response.status(), Status::Locked);
+	assert_eq!(body(response).await["status"], 423);
+}
+
+#[rocket::async_test]
+async fn signup_to_open_project() {
+	let client = client().await;
+
+	let credentials =
+		json!({ "username": "bob", "password": "bob-password", "project": "projects:open" });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	assert_eq!(user["username"], "bob");
+	assert_eq!(user["role"], "parti");
+	assert_eq!(user["project"]["id"], "projects:open");
+	assert!(user["pending"].is_null());
+
+	// the password was stored hashed, it works for logging in
+	let response = login(&client, "bob", "bob-password").await;
+	assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn signup_with_invitation_takes_its_role_once() {
+	let client = client().await;
+
+	let credentials =
+		json!({ "username": "carol", "password": "carol-password", "code": "WELCOME" });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	assert_eq!(user["role"], "thera");
+	assert_eq!(user["project"]["id"], "projects:invite");
+
+	let credentials =
+		json!({ "username": "dave", "password": "dave-password", "code": "WELCOME" });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn signup_errors() {
+	let client = client().await;
+
+	let cases = [
+		("alice", json!({ "project": "projects:open" }), Status::Conflict),
+		("erin", json!({ "project": "projects:invite" }), Status::Forbidden),
+		("erin", json!({ "project": "projects:missing" }), Status::NotFound),
+		("erin", json!({ "project": "projects:finished" }), Status::UnprocessableEntity),
+		("erin", json!({ "project": "centers:north" }), Status::UnprocessableEntity),
+		("erin", json!({ "code": "NOPE" }), Status::Forbidden),
+		("erin", json!({}), Status::BadRequest),
+		("erin", json!({ "code": "WELCOME", "project": "projects:open" }), Status::BadRequest),
+	];
+
+	for (username, mut credentials, status) in cases {
+		credentials["username"] = username.into();
+		credentials["password"] = "x".into();
+
+		let response = post(&client, "/auth/signup", credentials.clone()).await;
+		assert_eq!(response.status(), status, "{credentials}");
+	}
+
+	// the failed signups left nothing behind, not even a used invitation
+	let credentials = json!({ "username": "erin", "password": "x", "code": "WELCOME" });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn refresh_keeps_the_session() {
+	let client = client().await;
+	let token = token(&client, "alice", "alice-password").await;
+
+	let response = client.get("/auth/refresh").header(bearer(&token)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	assert_eq!(user["id"], "users:alice");
+
+	let refreshed = user["g_token"].as_str().expect("global token");
+	let response = client.get("/auth/refresh").header(bearer(refreshed)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+}
+
+/// `admin` coordinates a south center too, `alice` helps there while inside a north project
+const SOUTH: &str = r#"
+	CREATE centers:south SET name = 'South';
+	RELATE users:admin->roled->centers:south SET role = 'coord';
+	RELATE users:alice->roled->centers:south SET role = 'thera';
+"#;
+
+async fn select<'c>(client: &'c Client, token: &str, center: &str) -> LocalResponse<'c> {
+	client
+		.post("/auth/center")
+		.header(bearer(token))
+		.header(ContentType::JSON)
+		.body(json!({ "center": center }).to_string())
+		.dispatch()
+		.await
+}
+
+#[rocket::async_test]
+async fn center_selection() {
+	let client = client().await;
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.south", SOUTH).await.expect("south sent").check().expect("south center");
+	let admin = token(&client, "admin", "admin-password").await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response = select(&client, &admin, "centers:south").await;
+	assert_eq!(response.status(), Status::Ok);
+	let user = body(response).await;
+	assert_eq!(user["center"], "centers:south");
+	assert_eq!(user["role"], "coord");
+	let selected = user["g_token"].as_str().expect("global token");
+	assert_eq!(claims(selected)["center"], "centers:south");
+
+	// the refreshed tokens stay in the selected center
+	let response = client.get("/auth/refresh").header(bearer(selected)).dispatch().await;
+	assert_eq!(body(response).await["center"], "centers:south");
+
+	let response = select(&client, &admin, "centers:missing").await;
+	assert_eq!(response.status(), Status::Forbidden);
+
+	// the project token only exists in the center of the project
+	let response = select(&client, &alice, "centers:south").await;
+	assert_eq!(response.status(), Status::Conflict);
+	let response = select(&client, &alice, "centers:north").await;
+	assert_eq!(response.status(), Status::Ok);
+	let user = body(response).await;
+	assert_eq!(user["role"], "parti");
+	assert!(user["p_token"].as_str().is_some_and(|token| !token.is_empty()));
+
+	let response = client
+		.post("/auth/center")
+		.header(ContentType::JSON)
+		.body(json!({ "center": "centers:north" }).to_string())
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn logout_revokes_every_token_of_the_session() {
+	let client = client().await;
+	let token = token(&client, "alice", "alice-password").await;
+
+	let response = client.post("/auth/logout").header(bearer(&token)).dispatch().await;
+	assert_eq!(response.status(), Status::NoContent);
+
+	let response = client.get("/auth/refresh").header(bearer(&token)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "revoked_session");
+}
+
+#[rocket::async_test]
+async fn session_belongs_to_the_subject() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	// alice's live session under the id of another user
+	let mut forged = claims(&alice);
+	forged["id"] = "users:admin".into();
+	forged["role"] = "admin".into();
+	let forged = sign(&forged, SECRET_KEY.as_bytes());
+
+	let response = client.get("/auth/refresh").header(bearer(&forged)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "revoked_session");
+
+	let response = client.get("/auth/refresh").header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn disabled_and_deleted_users_lose_their_tokens() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response =
+		client.post("/admin/users/alice/disable").header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let response = client.get("/auth/refresh").header(bearer(&alice)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "revoked_session");
+
+	let credentials =
+		json!({ "username": "frank", "password": "x", "project": "projects:open" });
+	let frank = body(post(&client, "/auth/signup", credentials).await).await;
+	let id = frank["id"].as_str().expect("user id");
+	let frank = frank["g_token"].as_str().expect("global token");
+
+	let uri = format!("/admin/users/{id}");
+	let response = client.delete(uri).header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::NoContent);
+
+	let response = client.get("/auth/refresh").header(bearer(frank)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn password_change() {
+	let client = client().await;
+	let token = token(&client, "alice", "alice-password").await;
+
+	let change = json!({ "password": "wrong", "new_password": "alice-new" });
+	let response = client
+		.post("/auth/password")
+		.header(bearer(&token))
+		.header(ContentType::JSON)
+		.body(change.to_string())
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::Unauthorized);
+
+	let change = json!({ "password": "alice-password", "new_password": "alice-new" });
+	let response = client
+		.post("/auth/password")
+		.header(bearer(&token))
+		.header(ContentType::JSON)
+		.body(change.to_string())
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::NoContent);
+
+	assert_eq!(login(&client, "alice", "alice-password").await.status(), Status::Unauthorized);
+	assert_eq!(login(&client, "alice", "alice-new").await.status(), Status::Ok);
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{json, Value};
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{body, claims, client, login, post, sign, token};
+
+/// A therapist of the north center, with no membership of their own
+const THERAPIST: &str = r#"
+	CREATE users:tess SET username = 'tess', password = 'tess-password';
+	RELATE users:tess->roled->centers:north SET role = 'thera';
+"#;
+
+async fn decide(client: &Client, token: &str, action: &str, kind: &str, id: &str) -> Value {
+	let request = json!({
+		"token": token,
+		"action": action,
+		"resource": { "kind": kind, "id": id },
+	});
+
+	let response = post(client, "/auth/authorize", request).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	body(response).await
+}
+
+#[rocket::async_test]
+async fn admin_is_allowed_everywhere() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let decision = decide(&client, &admin, "users:read", "user", "users:alice").await;
+	assert_eq!(decision, json!({ "allow": true, "reason": "admin" }));
+
+	let decision = decide(&client, &admin, "projects:manage", "project", "projects:open").await;
+	assert_eq!(decision["allow"], true);
+}
+
+#[rocket::async_test]
+async fn participants_reach_themselves_and_their_project() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let decision = decide(&client, &alice, "answers:write", "user", "users:alice").await;
+	assert_eq!(decision, json!({ "allow": true, "reason": "own user" }));
+
+	let decision = decide(&client, &alice, "answers:write", "user", "users:admin").await;
+	assert_eq!(decision, json!({ "allow": false, "reason": "no relation to the user" }));
+
+	let decision = decide(&client, &alice, "answers:write", "project", "projects:open").await;
+	assert_eq!(decision, json!({ "allow": true, "reason": "member of the project" }));
+
+	let decision = decide(&client, &alice, "answers:write", "project", "projects:invite").await;
+	assert_eq!(decision["allow"], false);
+
+	let decision = decide(&client, &alice, "answers:write", "center", "centers:north").await;
+	assert_eq!(decision["allow"], true);
+
+	// the token must carry the permission of the action
+	let decision = decide(&client, &alice, "users:read", "user", "users:alice").await;
+	assert_eq!(decision, json!({ "allow": false, "reason": "missing permission users:read" }));
+}
+
+#[rocket::async_test]
+async fn staff_reach_the_users_of_their_center() {
+	let client = client().await;
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.therapist", THERAPIST)
+		.await
+		.expect("therapist sent")
+		.check()
+		.expect("therapist created");
+
+	let tess = token(&client, "tess", "tess-password").await;
+
+	let decision = decide(&client, &tess, "users:read", "user", "users:alice").await;
+	assert_eq!(decision, json!({ "allow": true, "reason": "thera in a center of the user" }));
+
+	let decision = decide(&client, &tess, "projects:read", "project", "projects:open").await;
+	assert_eq!(decision["reason"], "thera in the project center");
+
+	let decision = decide(&client, &tess, "users:read", "user", "users:admin").await;
+	assert_eq!(decision["allow"], false);
+}
+
+#[rocket::async_test]
+async fn batch_answers_each_resource() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let request = json!({
+		"token": alice,
+		"action": "answers:write",
+		"resources": [
+			{ "kind": "user", "id": "users:alice" },
+			{ "kind": "user", "id": "users:nobody" },
+			{ "kind": "user", "id": "projects:open" },
+			{ "kind": "project", "id": "projects:open" },
+		],
+	});
+	let response = post(&client, "/auth/authorize/batch", request).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let decisions = body(response).await;
+	let reasons: Vec<&str> =
+		decisions.as_array().unwrap().iter().map(|d| d["reason"].as_str().unwrap()).collect();
+	assert_eq!(
+		reasons,
+		["own user", "resource not found", "invalid resource id", "member of the project"]
+	);
+	assert_eq!(decisions[0]["resource"], json!({ "kind": "user", "id": "users:alice" }));
+}
+
+#[rocket::async_test]
+async fn only_global_tokens_of_open_sessions_are_subjects() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response = login(&client, "alice", "alice-password").await;
+	let alice = body(response).await;
+	let p_token = alice["p_token"].as_str().expect("project token");
+	let g_token = alice["g_token"].as_str().expect("global token");
+
+	let invalid = json!({ "allow": false, "reason": "invalid subject token" });
+
+	// the project token of a real member is not a subject token
+	let decision = decide(&client, p_token, "answers:write", "user", "users:alice").await;
+	assert_eq!(decision, invalid);
+
+	// nor is one a project service signs with its secret, here for the admin
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	let mut query =
+		db.query("test.secret", "RETURN projects:open.token;").await.expect("secret read");
+	let secret: Option<String> = query.take(0).expect("project secret");
+	let mut forged = claims(&admin);
+	forged["db"] = "Open".into();
+	let forged = sign(&forged, secret.expect("project secret").as_bytes());
+	let decision = decide(&client, &forged, "users:manage", "user", "users:alice").await;
+	assert_eq!(decision, invalid);
+
+	let decision = decide(&client, "not a token", "answers:write", "user", "users:alice").await;
+	assert_eq!(decision, invalid);
+
+	let response = client.post("/auth/logout").header(common::bearer(g_token)).dispatch().await;
+	assert_eq!(response.status(), Status::NoContent);
+	let decision = decide(&client, g_token, "answers:write", "user", "users:alice").await;
+	assert_eq!(decision, invalid);
+}
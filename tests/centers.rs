@@ -0,0 +1,118 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{LocalRequest, LocalResponse};
+use rocket::serde::json::{json, Value};
+
+use common::{bearer, body, client, get, token};
+
+async fn send<'c>(request: LocalRequest<'c>, admin: &str, body: Value) -> LocalResponse<'c> {
+	request
+		.header(bearer(admin))
+		.header(ContentType::JSON)
+		.body(body.to_string())
+		.dispatch()
+		.await
+}
+
+#[rocket::async_test]
+async fn create_rename_and_delete_a_center() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response =
+		send(client.post("/admin/centers"), &admin, json!({ "name": "South" })).await;
+	assert_eq!(response.status(), Status::Ok);
+	let center = body(response).await;
+	assert_eq!(center["projects"], 0);
+	let id = center["id"].as_str().expect("center id").to_owned();
+
+	let response =
+		send(client.post("/admin/centers"), &admin, json!({ "name": "North" })).await;
+	assert_eq!(response.status(), Status::Conflict);
+
+	let uri = format!("/admin/centers/{id}");
+	let rename = client.patch(uri.clone());
+	let response = send(rename, &admin, json!({ "name": "Southeast" })).await;
+	assert_eq!(body(response).await["name"], "Southeast");
+
+	let page = body(get(&client, "/admin/centers", &admin).await).await;
+	let names: Vec<&str> =
+		page["items"].as_array().unwrap().iter().map(|c| c["name"].as_str().unwrap()).collect();
+	assert_eq!(names, ["North", "Southeast"]);
+	assert_eq!(page["items"][0]["projects"], 3);
+	assert_eq!(page["items"][0]["staff"], 1);
+
+	let response = client.delete(uri.clone()).header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::NoContent);
+	assert_eq!(get(&client, &uri, &admin).await.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn center_with_projects_stays() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response =
+		client.delete("/admin/centers/north").header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::Conflict);
+
+	let page =
+		body(get(&client, "/admin/centers/north/projects?per_page=2", &admin).await).await;
+	assert_eq!(page["items"].as_array().unwrap().len(), 2);
+	assert_eq!(page["total"], 3);
+	// the full record id names the same center
+	let page = body(get(&client, "/admin/centers/centers:north/projects", &admin).await).await;
+	assert_eq!(page["total"], 3);
+
+	let response = get(&client, "/admin/centers/south/projects", &admin).await;
+	assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn staff_is_assigned_and_removed() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	// alice goes from participant to coordinator, the role is replaced
+	let assign = client.put("/admin/centers/north/staff/alice");
+	let response = send(assign, &admin, json!({ "role": "coord" })).await;
+	assert_eq!(response.status(), Status::Ok);
+	let staff = body(response).await;
+	assert_eq!(
+		staff,
+		json!([
+			{ "id": "users:admin", "username": "admin", "role": "admin" },
+			{ "id": "users:alice", "username": "alice", "role": "coord" },
+		])
+	);
+
+	let user = body(get(&client, "/admin/users/alice", &admin).await).await;
+	assert_eq!(user["roles"].as_array().unwrap().len(), 1);
+
+	let assign = client.put("/admin/centers/north/staff/nobody");
+	let response = send(assign, &admin, json!({ "role": "coord" })).await;
+	assert_eq!(response.status(), Status::NotFound);
+
+	let remove = "/admin/centers/north/staff/alice";
+	let response = client.delete(remove).header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::NoContent);
+	let response = client.delete(remove).header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::NotFound);
+
+	let staff = body(get(&client, "/admin/centers/north/staff", &admin).await).await;
+	assert_eq!(staff.as_array().unwrap().len(), 1);
+}
+
+#[rocket::async_test]
+async fn centers_are_for_admins() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response = get(&client, "/admin/centers", &alice).await;
+	assert_eq!(response.status(), Status::Forbidden);
+
+	let assign = client.put("/admin/centers/north/staff/alice");
+	let response = send(assign, &alice, json!({ "role": "admin" })).await;
+	assert_eq!(response.status(), Status::Forbidden);
+}
@@ -0,0 +1,121 @@
+//! Every test gets its own server, on its own in memory store seeded with the same accounts
+#![allow(dead_code)]
+
+use std::sync::Once;
+
+use jsonwebtoken::{DecodingKey, EncodingKey, Validation};
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{json, Value};
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+use q_api_auth::app::server;
+
+pub const SECRET_KEY: &str = "itV5oEbErQuRObmAG2aXwmQHwM4xD/CN1fmxQT3272U=";
+pub const ORIGIN: &str = "http://localhost:8000";
+
+/// A center with an open project, an invite only one and a finished one. `alice` takes part
+/// in the open project, `admin` runs the center and `sleepy` is on standby
+const SEED: &str = r#"
+	BEGIN TRANSACTION;
+
+	CREATE centers:north SET name = 'North';
+
+	CREATE projects:open SET name = 'Open', state = 'active', signup = 'open',
+		token = rand::string(48), center = centers:north;
+	CREATE projects:invite SET name = 'Invite', state = 'active', signup = 'invite',
+		token = rand::string(48), center = centers:north;
+	CREATE projects:finished SET name = 'Finished', state = 'finished', signup = 'open',
+		token = rand::string(48), center = centers:north;
+	RELATE projects:open->belongs->centers:north;
+	RELATE projects:invite->belongs->centers:north;
+	RELATE projects:finished->belongs->centers:north;
+
+	CREATE users:alice SET username = 'alice', password = 'alice-password',
+		project = projects:open;
+	RELATE users:alice->join->projects:open SET pending = false, requested_at = time::now();
+	RELATE users:alice->roled->centers:north SET role = 'parti';
+
+	CREATE users:admin SET username = 'admin', password = 'admin-password';
+	RELATE users:admin->roled->centers:north SET role = 'admin';
+
+	CREATE users:sleepy SET username = 'sleepy', password = 'sleepy-password',
+		project = projects:open, state = 'standby';
+	RELATE users:sleepy->roled->centers:north SET role = 'parti';
+
+	CREATE invitations:welcome SET code = 'WELCOME', project = projects:invite, role = 'thera',
+		uses = 0, max_uses = 1, expires_at = time::now() + 1d, revoked = false;
+
+	COMMIT TRANSACTION;
+"#;
+
+static CONFIG: Once = Once::new();
+
+/// The `local` profile, with a known key, a quick webhook worker and no consistency check
+fn configure() {
+	CONFIG.call_once(|| {
+		std::env::set_var("ROCKET_PROFILE", "local");
+		std::env::set_var("ROCKET_SECRET_KEY", SECRET_KEY);
+		std::env::set_var("ROCKET_LOGGING", r#"{level="error"}"#);
+		std::env::set_var("ROCKET_WEBHOOKS", "{poll=1,backoff=1}");
+		std::env::set_var("ROCKET_CONSISTENCY", "{interval=0}");
+	});
+}
+
+pub async fn client() -> Client {
+	configure();
+
+	let client = Client::tracked(server::rocket().await).await.expect("valid rocket instance");
+
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.seed", SEED).await.expect("seed sent").check().expect("seed applied");
+
+	client
+}
+
+pub fn bearer(token: &str) -> Header<'static> {
+	Header::new("Authorization", format!("Bearer {token}"))
+}
+
+pub async fn get<'c>(client: &'c Client, uri: &'c str, token: &str) -> LocalResponse<'c> {
+	client.get(uri).header(bearer(token)).dispatch().await
+}
+
+pub async fn post<'c>(client: &'c Client, uri: &'c str, body: Value) -> LocalResponse<'c> {
+	client.post(uri).header(ContentType::JSON).body(body.to_string()).dispatch().await
+}
+
+pub async fn login<'c>(
+	client: &'c Client,
+	username: &str,
+	password: &str,
+) -> LocalResponse<'c> {
+	post(client, "/auth/login", json!({ "username": username, "password": password })).await
+}
+
+/// Global token of a successful login
+pub async fn token(client: &Client, username: &str, password: &str) -> String {
+	let response = login(client, username, password).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	user["g_token"].as_str().expect("global token").to_owned()
+}
+
+/// Claims of a token, without checking its signature
+pub fn claims(token: &str) -> Value {
+	let mut validation = Validation::default();
+	validation.insecure_disable_signature_validation();
+
+	let key = DecodingKey::from_secret(&[]);
+	jsonwebtoken::decode::<Value>(token, &key, &validation).expect("token decoded").claims
+}
+
+pub fn sign(claims: &Value, key: &[u8]) -> String {
+	let key = EncodingKey::from_secret(key);
+	jsonwebtoken::encode(&jsonwebtoken::Header::default(), claims, &key).expect("token encoded")
+}
+
+pub async fn body(response: LocalResponse<'_>) -> Value {
+	response.into_json::<Value>().await.expect("json body")
+}
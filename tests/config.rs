@@ -0,0 +1,173 @@
+mod common;
+
+use jsonwebtoken::{DecodingKey, Validation};
+use rocket::figment::providers::{Format, Toml};
+use rocket::figment::Figment;
+use rocket::http::Status;
+use rocket::serde::json::Value;
+
+use q_api_auth::app::providers::config::getter::Signin;
+use q_api_auth::app::providers::config::settings::Settings;
+
+use common::{body, client, login, SECRET_KEY};
+
+/// The shipped `Rocket.toml` in its `debug` profile with a usable key, overridden by `values`
+fn figment(values: &str) -> Figment {
+	Figment::new()
+		.merge(Toml::file(concat!(env!("CARGO_MANIFEST_DIR"), "/Rocket.toml")).nested())
+		.merge(Toml::string(&format!(r#"secret_key = "{SECRET_KEY}""#)))
+		.merge(Toml::string(values).profile("debug"))
+		.select("debug")
+}
+
+fn problems(values: &str) -> Vec<String> {
+	Settings::extract(&figment(values)).expect_err("the configuration should be refused")
+}
+
+#[test]
+fn shipped_configuration_is_valid() {
+	let settings = Settings::extract(&figment("")).expect("the configuration should load");
+
+	assert_eq!(settings.origins, ["http://localhost:8000", "http://localhost:8080"]);
+	assert_eq!(settings.token_ttl, 86400);
+	assert_eq!(settings.databases.store.pool.size, 4);
+	assert_eq!(settings.databases.store.signin, Signin::Root);
+}
+
+#[test]
+fn short_secret_key_is_refused() {
+	let problems = problems(r#"secret_key = "too-short""#);
+
+	assert_eq!(problems.len(), 1);
+	assert!(problems[0].contains("secret_key"));
+}
+
+#[test]
+fn origins_must_be_origins() {
+	let problems =
+		problems(r#"origin_url = "http://localhost:8000, localhost, https://a.b/path""#);
+
+	assert_eq!(problems.len(), 2);
+	assert!(problems[0].contains("`localhost`"));
+	assert!(problems[1].contains("`https://a.b/path`"));
+
+	let problems = self::problems(r#"origin_url = " , ""#);
+	assert!(problems[0].contains("no origin"));
+
+	let settings = Settings::extract(&figment(r#"origin_url = "https://*.example.com""#))
+		.expect("a wildcard subdomain is an origin");
+	assert_eq!(settings.origins, ["https://*.example.com"]);
+
+	let problems = self::problems(r#"origin_url = "https://*, https://a.*.com""#);
+	assert_eq!(problems.len(), 2);
+}
+
+#[test]
+fn cors_methods_are_methods() {
+	let problems = problems(r#"cors = { methods = ["GET", "FETCH"] }"#);
+
+	assert_eq!(problems.len(), 1);
+	assert!(problems[0].contains("`FETCH`"));
+}
+
+#[test]
+fn every_problem_is_reported() {
+	let problems = problems(
+		r#"
+		token_ttl = 10
+		[databases.store.pool]
+		size = 0
+		[webhooks]
+		max_attempts = 0
+		"#,
+	);
+
+	assert_eq!(problems.len(), 3);
+	assert!(problems.iter().any(|problem| problem.contains("token_ttl")));
+	assert!(problems.iter().any(|problem| problem.contains("pool.size")));
+	assert!(problems.iter().any(|problem| problem.contains("max_attempts")));
+}
+
+#[test]
+fn missing_values_are_reported() {
+	let problems = Settings::extract(&Figment::new()).expect_err("nothing is configured");
+
+	assert!(problems.iter().any(|problem| problem.contains("origin_url")));
+}
+
+#[test]
+fn server_needs_a_username() {
+	let problems = Figment::new()
+		.merge(Toml::file(concat!(env!("CARGO_MANIFEST_DIR"), "/Rocket.toml")).nested())
+		.merge(Toml::string(&format!(r#"secret_key = "{SECRET_KEY}""#)))
+		.select("release");
+	let problems = Settings::extract(&problems).expect_err("no credentials in release");
+
+	assert_eq!(problems.len(), 1);
+	assert!(problems[0].contains("username"));
+}
+
+#[test]
+fn secrets_are_read_from_files() {
+	let dir = std::env::temp_dir().join(format!("q-api-auth-secrets-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	std::fs::write(dir.join("secret_key"), format!("{SECRET_KEY}\n")).unwrap();
+	std::fs::write(dir.join("password"), "from-file\n").unwrap();
+
+	let figment = figment(&format!(
+		r#"
+		secret_key = ""
+		secret_key_file = "{}"
+		[databases.store]
+		signin = "database"
+		username = "auth"
+		password_file = "{}"
+		"#,
+		dir.join("secret_key").display(),
+		dir.join("password").display(),
+	));
+	let figment = Settings::read_secrets(figment).expect("the files should be read");
+	let settings = Settings::extract(&figment).expect("the configuration should load");
+
+	assert_eq!(settings.secret_key, SECRET_KEY);
+	assert_eq!(settings.databases.store.signin, Signin::Database);
+	assert_eq!(settings.databases.store.username, "auth");
+	assert_eq!(settings.databases.store.password, "from-file");
+
+	std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unreadable_secret_file_is_reported() {
+	let figment = figment(r#"secret_key_file = "/nonexistent/secret_key""#);
+	let problems = Settings::read_secrets(figment).expect_err("the file does not exist");
+
+	assert_eq!(problems.len(), 1);
+	assert!(problems[0].contains("secret_key_file"));
+	assert!(problems[0].contains("/nonexistent/secret_key"));
+}
+
+#[test]
+fn nested_values_from_the_environment() {
+	std::env::set_var("ROCKET_WEBHOOKS__MAX_ATTEMPTS", "3");
+
+	let attempts: u32 = Settings::figment().extract_inner("webhooks.max_attempts").unwrap();
+	assert_eq!(attempts, 3);
+}
+
+#[rocket::async_test]
+async fn tokens_last_token_ttl() {
+	std::env::set_var("ROCKET_TOKEN_TTL", "600");
+	let client = client().await;
+
+	let response = login(&client, "alice", "alice-password").await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	let token = user["g_token"].as_str().unwrap();
+	let key = DecodingKey::from_secret(SECRET_KEY.as_bytes());
+	let claims =
+		jsonwebtoken::decode::<Value>(token, &key, &Validation::default()).unwrap().claims;
+
+	assert_eq!(claims["exp"].as_i64().unwrap() - claims["iat"].as_i64().unwrap(), 600);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::{json, Value};
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{bearer, body, client, get, post, token};
+
+/// `bob` joined the open project himself and `carol` through the invitation, then both lost
+/// their role. `alice` lost her role too, her membership predates the admission record, and
+/// `sleepy` lost the membership itself
+const BREAK: &str = r#"
+	DELETE roled WHERE in INSIDE [users:alice, users:bob, users:carol];
+	DELETE join WHERE in = users:sleepy;
+"#;
+
+async fn roles(db: &DbAuth, user: &str) -> Vec<String> {
+	let mut query = db
+		.query("test.roles", "SELECT VALUE role FROM roled WHERE in = <record> $b_user;")
+		.bind(("b_user", user))
+		.await
+		.expect("roles read");
+
+	query.take(0).expect("roles")
+}
+
+#[rocket::async_test]
+async fn fix_gives_back_the_admitted_role() {
+	let client = client().await;
+
+	let mut ids = Vec::new();
+	let signups = [
+		json!({ "username": "bob", "password": "bob-password", "project": "projects:open" }),
+		json!({ "username": "carol", "password": "carol-password", "code": "WELCOME" }),
+	];
+	for credentials in signups {
+		let response = post(&client, "/auth/signup", credentials).await;
+		assert_eq!(response.status(), Status::Ok);
+		ids.push(body(response).await["id"].as_str().expect("user id").to_owned());
+	}
+	let (bob, carol) = (&ids[0], &ids[1]);
+
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	let sql = BREAK.replace("users:bob", bob).replace("users:carol", carol);
+	db.query("test.break", sql).await.expect("break sent").check().expect("edges removed");
+
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response = get(&client, "/admin/consistency", &admin).await;
+	assert_eq!(response.status(), Status::Ok);
+	let report = body(response).await;
+	assert_eq!(report["missing_join"], json!(["users:sleepy"]));
+	assert_eq!(report["missing_roled"].as_array().unwrap().len(), 3);
+	assert_eq!(report["fixed"], false);
+
+	let response =
+		client.post("/admin/consistency/fix").header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+	let report = body(response).await;
+	assert_eq!(report["fixed"], true);
+	assert_eq!(report["unresolved"], json!(["users:alice"]));
+
+	assert_eq!(roles(db, bob).await, ["parti"]);
+	assert_eq!(roles(db, carol).await, ["thera"]);
+
+	// only alice is left, for an admin to decide
+	let report: Value = body(get(&client, "/admin/consistency", &admin).await).await;
+	assert_eq!(report["missing_join"], json!([]));
+	assert_eq!(report["missing_roled"], json!(["users:alice"]));
+}
+
+#[rocket::async_test]
+async fn consistency_is_for_admins() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response = get(&client, "/admin/consistency", &alice).await;
+	assert_eq!(response.status(), Status::Forbidden);
+
+	let response =
+		client.post("/admin/consistency/fix").header(bearer(&alice)).dispatch().await;
+	assert_eq!(response.status(), Status::Forbidden);
+}
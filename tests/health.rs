@@ -0,0 +1,30 @@
+//! The store signed in as a user defined on the database, the least a deployment may give us
+mod common;
+
+use rocket::http::Status;
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{body, client, login};
+
+#[rocket::async_test]
+async fn ready_as_a_database_user() {
+	std::env::set_var("ROCKET_DATABASES__STORE__SIGNIN", "database");
+	std::env::set_var("ROCKET_DATABASES__STORE__USERNAME", "auth");
+	std::env::set_var("ROCKET_DATABASES__STORE__PASSWORD", "auth-password");
+	let client = client().await;
+
+	// nothing above the database is visible to this user
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	let info = db.query("test.info", "INFO FOR NS;").await.expect("info sent").check();
+	assert!(info.is_err());
+
+	let response = client.get("/health/ready").dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let readiness = body(response).await;
+	assert_eq!(readiness["ready"], true, "{readiness}");
+
+	let response = login(&client, "alice", "alice-password").await;
+	assert_eq!(response.status(), Status::Ok);
+}
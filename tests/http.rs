@@ -0,0 +1,221 @@
+mod common;
+
+use std::borrow::Cow;
+
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rocket::http::{ContentType, Header as HttpHeader, Status};
+use rocket::serde::json::json;
+
+use q_api_auth::app::providers::config::cors::is_allowed;
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{bearer, body, client, post, token, ORIGIN, SECRET_KEY};
+
+#[rocket::async_test]
+async fn missing_and_malformed_tokens() {
+	let client = client().await;
+
+	let response = client.get("/auth/refresh").dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+
+	let error = body(response).await;
+	assert_eq!(error["status"], 401);
+	assert_eq!(error["error"], "missing_token");
+	assert_eq!(error["message"], "Authorization header is missing");
+
+	let response = client.get("/auth/refresh").header(bearer("not.a.token")).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "invalid_token");
+}
+
+#[rocket::async_test]
+async fn forged_and_expired_tokens() {
+	let client = client().await;
+	let now = chrono::Utc::now().timestamp();
+
+	let sign = |key: &[u8], exp: i64| {
+		let claims = json!({
+			"ns": "global", "db": "main", "sc": "user", "tk": "user_scope",
+			"id": "users:alice", "role": "parti", "sid": "sessions:any",
+			"iat": now - 60, "exp": exp,
+		});
+		let key = EncodingKey::from_secret(key);
+		encode(&Header::default(), &claims, &key).expect("token encoded")
+	};
+
+	let forged = sign(b"another key", now + 3600);
+	let response = client.get("/auth/refresh").header(bearer(&forged)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "invalid_token");
+
+	let expired = sign(SECRET_KEY.as_bytes(), now - 3600);
+	let response = client.get("/auth/refresh").header(bearer(&expired)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "invalid_token");
+
+	// well signed, for a session that was never opened
+	let unknown = sign(SECRET_KEY.as_bytes(), now + 3600);
+	let response = client.get("/auth/refresh").header(bearer(&unknown)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "revoked_session");
+}
+
+#[rocket::async_test]
+async fn role_guards() {
+	let client = client().await;
+
+	let alice = token(&client, "alice", "alice-password").await;
+	let response = client.get("/admin/webhooks").header(bearer(&alice)).dispatch().await;
+	assert_eq!(response.status(), Status::Forbidden);
+	assert_eq!(body(response).await["error"], "insufficient_role");
+
+	let admin = token(&client, "admin", "admin-password").await;
+	let response = client.get("/admin/webhooks").header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn malformed_bodies() {
+	let client = client().await;
+
+	let response = client
+		.post("/auth/login")
+		.header(ContentType::JSON)
+		.body("{ not json")
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::BadRequest);
+
+	let response = post(&client, "/auth/login", json!({ "username": "alice" })).await;
+	assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[rocket::async_test]
+async fn cors_headers() {
+	let client = client().await;
+
+	let response = client
+		.options("/auth/login")
+		.header(HttpHeader::new("Origin", ORIGIN))
+		.header(HttpHeader::new("Access-Control-Request-Method", "POST"))
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::NoContent);
+
+	let headers = response.headers();
+	assert_eq!(headers.get_one("Access-Control-Allow-Origin"), Some(ORIGIN));
+	assert_eq!(headers.get_one("Access-Control-Allow-Credentials"), Some("true"));
+	assert_eq!(headers.get_one("Access-Control-Max-Age"), Some("3600"));
+	assert!(headers.get("Vary").any(|vary| vary == "Origin"));
+	let methods = headers.get_one("Access-Control-Allow-Methods").unwrap_or_default();
+	assert!(methods.contains("POST") && methods.contains("DELETE"));
+	let allowed = headers.get_one("Access-Control-Allow-Headers").unwrap_or_default();
+	assert!(allowed.contains("Authorization"));
+
+	// every mounted route has its preflight, not only the auth ones
+	let response = client
+		.options("/admin/users/alice")
+		.header(HttpHeader::new("Origin", ORIGIN))
+		.header(HttpHeader::new("Access-Control-Request-Method", "DELETE"))
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::NoContent);
+	assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), Some(ORIGIN));
+
+	// error responses carry them too, or the browser hides the error from the page
+	let response =
+		client.get("/auth/refresh").header(HttpHeader::new("Origin", ORIGIN)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	let headers = response.headers();
+	assert_eq!(headers.get_one("Access-Control-Allow-Origin"), Some(ORIGIN));
+	assert_eq!(headers.get_one("Access-Control-Expose-Headers"), Some("X-Request-Id"));
+	assert!(headers.get_one("Access-Control-Allow-Methods").is_none());
+
+	let response = client
+		.get("/health")
+		.header(HttpHeader::new("Origin", "http://elsewhere.example"))
+		.dispatch()
+		.await;
+	let headers = response.headers();
+	assert!(headers.get_one("Access-Control-Allow-Origin").is_none());
+	assert!(headers.get_one("Access-Control-Allow-Credentials").is_none());
+	assert!(headers.get("Vary").any(|vary| vary == "Origin"));
+}
+
+#[test]
+fn cors_origin_matching() {
+	let origins = ["https://app.example.com", "https://*.example.org", "http://*.local:8080"]
+		.map(Cow::from);
+
+	assert!(is_allowed(&origins, "https://app.example.com"));
+	assert!(!is_allowed(&origins, "https://app.example.com.evil.io"));
+	assert!(!is_allowed(&origins, "http://app.example.com"));
+
+	assert!(is_allowed(&origins, "https://a.example.org"));
+	assert!(is_allowed(&origins, "https://a.b.EXAMPLE.org"));
+	assert!(!is_allowed(&origins, "https://example.org"));
+	assert!(!is_allowed(&origins, "https://evilexample.org"));
+	assert!(!is_allowed(&origins, "http://a.example.org"));
+	assert!(!is_allowed(&origins, "https://a.example.org:8443"));
+
+	assert!(is_allowed(&origins, "http://dev.local:8080"));
+	assert!(!is_allowed(&origins, "http://dev.local"));
+	assert!(!is_allowed(&origins, "http://x.com:80.local:8080"));
+}
+
+#[rocket::async_test]
+async fn health_and_version() {
+	let client = client().await;
+
+	let response = client.get("/health/live").dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let response = client.get("/health/ready").dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+	assert_eq!(body(response).await["ready"], true);
+
+	let response = client.get("/version").dispatch().await;
+	assert_eq!(body(response).await["name"], "q-api-auth");
+}
+
+#[rocket::async_test]
+async fn sessions_past_their_tokens_are_not_counted() {
+	let client = client().await;
+	token(&client, "alice", "alice-password").await;
+	token(&client, "admin", "admin-password").await;
+
+	let sessions = |metrics: String| {
+		metrics
+			.lines()
+			.find_map(|line| line.strip_prefix("auth_active_sessions "))
+			.map(str::to_owned)
+	};
+
+	let response = client.get("/metrics").dispatch().await;
+	assert_eq!(sessions(response.into_string().await.unwrap()).as_deref(), Some("2"));
+
+	// never refreshed since, every token of it has expired
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query(
+		"test.age_sessions",
+		"UPDATE sessions SET refreshed_at = time::now() - 2d WHERE user = users:alice;",
+	)
+	.await
+	.expect("session aged")
+	.check()
+	.expect("session aged");
+
+	let response = client.get("/metrics").dispatch().await;
+	assert_eq!(sessions(response.into_string().await.unwrap()).as_deref(), Some("1"));
+}
+
+#[rocket::async_test]
+async fn queries_are_labelled_by_name() {
+	let client = client().await;
+	token(&client, "alice", "alice-password").await;
+
+	let response = client.get("/metrics").dispatch().await;
+	let metrics = response.into_string().await.unwrap();
+	assert!(metrics.contains(r#"db_query_duration_seconds_count{query="auth.login"}"#));
+	assert!(!metrics.contains("handlers/"));
+}
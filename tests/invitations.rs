@@ -0,0 +1,127 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{json, Value};
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{bearer, body, client, get, post, token};
+
+/// `cora` coordinates the north center, `sam` a south center without projects
+const COORDINATORS: &str = r#"
+	CREATE centers:south SET name = 'South';
+	CREATE users:cora SET username = 'cora', password = 'cora-password';
+	RELATE users:cora->roled->centers:north SET role = 'coord';
+	CREATE users:sam SET username = 'sam', password = 'sam-password';
+	RELATE users:sam->roled->centers:south SET role = 'coord';
+"#;
+
+async fn coordinators(client: &Client) {
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.coordinators", COORDINATORS)
+		.await
+		.expect("coordinators sent")
+		.check()
+		.expect("coordinators");
+}
+
+async fn issue<'c>(client: &'c Client, token: &str, invitation: Value) -> LocalResponse<'c> {
+	client
+		.post("/invitations")
+		.header(bearer(token))
+		.header(ContentType::JSON)
+		.body(invitation.to_string())
+		.dispatch()
+		.await
+}
+
+#[rocket::async_test]
+async fn coordinator_issues_codes_for_their_center() {
+	let client = client().await;
+	coordinators(&client).await;
+	let cora = token(&client, "cora", "cora-password").await;
+
+	let invitation = json!({ "project": "projects:invite", "role": "parti", "max_uses": 2 });
+	let response = issue(&client, &cora, invitation).await;
+	assert_eq!(response.status(), Status::Ok);
+	let invitation = body(response).await;
+	assert_eq!(invitation["max_uses"], 2);
+	assert_eq!(invitation["uses"], 0);
+	assert_eq!(invitation["created_by"], "users:cora");
+	let code = invitation["code"].as_str().expect("invitation code");
+
+	let credentials = json!({ "username": "bob", "password": "bob-password", "code": code });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let listed = body(get(&client, "/invitations?project=projects:invite", &cora).await).await;
+	let listed = listed.as_array().expect("invitations");
+	// the seeded code is for the same project
+	assert_eq!(listed.len(), 2);
+	assert_eq!(listed.iter().find(|i| i["code"] == code).expect("issued code")["uses"], 1);
+}
+
+#[rocket::async_test]
+async fn only_coordinators_of_the_center_issue_codes() {
+	let client = client().await;
+	coordinators(&client).await;
+	let sam = token(&client, "sam", "sam-password").await;
+	let alice = token(&client, "alice", "alice-password").await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let invitation = json!({ "project": "projects:invite", "role": "thera" });
+	let response = issue(&client, &sam, invitation.clone()).await;
+	assert_eq!(response.status(), Status::Forbidden);
+	let response = issue(&client, &alice, invitation.clone()).await;
+	assert_eq!(response.status(), Status::Forbidden);
+	let response = issue(&client, &admin, invitation).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	// nothing of the north center for sam
+	let listed = body(get(&client, "/invitations", &sam).await).await;
+	assert_eq!(listed, json!([]));
+
+	let cases = [
+		(json!({ "project": "projects:invite", "role": "admin" }), Status::UnprocessableEntity),
+		(
+			json!({ "project": "projects:invite", "role": "parti", "max_uses": 0 }),
+			Status::UnprocessableEntity,
+		),
+		(
+			json!({ "project": "projects:invite", "role": "parti", "expires_in": 721 }),
+			Status::UnprocessableEntity,
+		),
+		(json!({ "project": "projects:missing", "role": "parti" }), Status::NotFound),
+	];
+	for (invitation, status) in cases {
+		let response = issue(&client, &admin, invitation.clone()).await;
+		assert_eq!(response.status(), status, "{invitation}");
+	}
+}
+
+#[rocket::async_test]
+async fn revoked_code_is_refused() {
+	let client = client().await;
+	coordinators(&client).await;
+	let sam = token(&client, "sam", "sam-password").await;
+	let cora = token(&client, "cora", "cora-password").await;
+
+	let response = client.delete("/invitations/WELCOME").header(bearer(&sam)).dispatch().await;
+	assert_eq!(response.status(), Status::Forbidden);
+	let response = client.delete("/invitations/WELCOME").header(bearer(&cora)).dispatch().await;
+	assert_eq!(response.status(), Status::NoContent);
+	let response = client.delete("/invitations/NOPE").header(bearer(&cora)).dispatch().await;
+	assert_eq!(response.status(), Status::NotFound);
+
+	let credentials =
+		json!({ "username": "bob", "password": "bob-password", "code": "WELCOME" });
+	let response = post(&client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Forbidden);
+
+	let listed = body(get(&client, "/invitations?active=false", &cora).await).await;
+	assert_eq!(listed[0]["code"], "WELCOME");
+	assert_eq!(listed[0]["revoked"], true);
+	let listed = body(get(&client, "/invitations?active=true", &cora).await).await;
+	assert_eq!(listed, json!([]));
+}
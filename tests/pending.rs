@@ -0,0 +1,100 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::json;
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{bearer, body, client, get, login, post, token};
+
+/// The open project asks for approval, `cora` coordinates its center and `sam` another one
+const APPROVAL: &str = r#"
+	UPDATE projects:open SET signup = 'approval';
+	CREATE centers:south SET name = 'South';
+	CREATE users:cora SET username = 'cora', password = 'cora-password';
+	RELATE users:cora->roled->centers:north SET role = 'coord';
+	CREATE users:sam SET username = 'sam', password = 'sam-password';
+	RELATE users:sam->roled->centers:south SET role = 'coord';
+"#;
+
+/// Signs `username` up to the open project, returns the new user id
+async fn request(client: &Client, username: &str) -> String {
+	let credentials =
+		json!({ "username": username, "password": "password", "project": "projects:open" });
+	let response = post(client, "/auth/signup", credentials).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(response).await;
+	assert_eq!(user["pending"], "projects:open");
+	assert!(user["project"].is_null());
+
+	user["id"].as_str().expect("user id").to_owned()
+}
+
+async fn decide(client: &Client, token: &str, user: &str, decision: &str) -> Status {
+	let uri = format!("/projects/open/pending/{user}/{decision}");
+
+	client.post(uri).header(bearer(token)).dispatch().await.status()
+}
+
+#[rocket::async_test]
+async fn coordinator_approves_and_rejects() {
+	let client = client().await;
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.approval", APPROVAL)
+		.await
+		.expect("approval sent")
+		.check()
+		.expect("approval set");
+
+	let bob = request(&client, "bob").await;
+	let carol = request(&client, "carol").await;
+
+	let cora = token(&client, "cora", "cora-password").await;
+	let pending = body(get(&client, "/projects/open/pending", &cora).await).await;
+	let usernames: Vec<&str> =
+		pending.as_array().unwrap().iter().map(|p| p["username"].as_str().unwrap()).collect();
+	assert_eq!(usernames, ["bob", "carol"]);
+
+	assert_eq!(decide(&client, &cora, &bob, "approve").await, Status::NoContent);
+	assert_eq!(decide(&client, &cora, &carol, "reject").await, Status::NoContent);
+	// each request is decided once
+	assert_eq!(decide(&client, &cora, &bob, "reject").await, Status::NotFound);
+
+	let bob = body(login(&client, "bob", "password").await).await;
+	assert_eq!(bob["project"]["id"], "projects:open");
+	assert!(bob["pending"].is_null());
+	let carol = body(login(&client, "carol", "password").await).await;
+	assert!(carol["project"].is_null());
+	assert!(carol["pending"].is_null());
+
+	let pending = body(get(&client, "/projects/open/pending", &cora).await).await;
+	assert_eq!(pending, json!([]));
+}
+
+#[rocket::async_test]
+async fn only_coordinators_of_the_center_decide() {
+	let client = client().await;
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.approval", APPROVAL)
+		.await
+		.expect("approval sent")
+		.check()
+		.expect("approval set");
+
+	let bob = request(&client, "bob").await;
+
+	let sam = token(&client, "sam", "sam-password").await;
+	let alice = token(&client, "alice", "alice-password").await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response = get(&client, "/projects/open/pending", &sam).await;
+	assert_eq!(response.status(), Status::Forbidden);
+	assert_eq!(decide(&client, &sam, &bob, "approve").await, Status::Forbidden);
+	assert_eq!(decide(&client, &alice, &bob, "approve").await, Status::Forbidden);
+
+	let response = get(&client, "/projects/missing/pending", &admin).await;
+	assert_eq!(response.status(), Status::NotFound);
+	assert_eq!(decide(&client, &admin, &bob, "approve").await, Status::NoContent);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{json, Value};
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{bearer, body, claims, client, login, token};
+
+/// `bob` waits for the approval of the open project, `cora` coordinates its center
+const PENDING: &str = r#"
+	UPDATE projects:open SET signup = 'approval';
+	CREATE users:bob SET username = 'bob', password = 'bob-password', pending = projects:open;
+	CREATE users:cora SET username = 'cora', password = 'cora-password';
+	RELATE users:cora->roled->centers:north SET role = 'coord';
+"#;
+
+async fn setup(client: &Client, sql: &str) {
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query("test.setup", sql).await.expect("setup sent").check().expect("setup");
+}
+
+async fn perms(client: &Client, username: &str, password: &str) -> Value {
+	let user = body(login(client, username, password).await).await;
+	let token = user["g_token"].as_str().expect("global token");
+	assert_eq!(claims(token)["perms"], user["perms"]);
+
+	user["perms"].clone()
+}
+
+#[rocket::async_test]
+async fn roles_get_the_default_perms() {
+	let client = client().await;
+
+	assert_eq!(perms(&client, "alice", "alice-password").await, json!(["answers:write"]));
+	let admin = perms(&client, "admin", "admin-password").await;
+	assert_eq!(admin.as_array().expect("admin perms").len(), 8);
+	assert!(admin.as_array().unwrap().contains(&json!("users:manage")));
+}
+
+#[rocket::async_test]
+async fn project_override_beats_the_global_one() {
+	let client = client().await;
+	// the global sets are seeded, the casts keep the names from being read as record ids
+	let overrides = r#"
+		UPDATE role_perms SET perms = [<string> 'answers:read'] WHERE role = 'parti' AND !project;
+		CREATE role_perms SET role = 'parti', project = projects:open,
+			perms = [<string> 'answers:read', <string> 'answers:write'];
+		CREATE users:erin SET username = 'erin', password = 'erin-password',
+			project = projects:finished;
+		RELATE users:erin->roled->centers:north SET role = 'parti';
+	"#;
+	setup(&client, overrides).await;
+
+	let alice = perms(&client, "alice", "alice-password").await;
+	assert_eq!(alice, json!(["answers:read", "answers:write"]));
+	// no override for her project, the global one
+	assert_eq!(perms(&client, "erin", "erin-password").await, json!(["answers:read"]));
+}
+
+#[rocket::async_test]
+async fn missing_permission_is_forbidden() {
+	let client = client().await;
+	setup(&client, PENDING).await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let approve = "/projects/open/pending/users:bob/approve";
+	let response = client.post(approve).header(bearer(&alice)).dispatch().await;
+	assert_eq!(response.status(), Status::Forbidden);
+	assert_eq!(body(response).await["error"], "missing_permission");
+
+	// coordinators keep their role but no longer manage users
+	let read_only =
+		"UPDATE role_perms SET perms = [<string> 'users:read'] WHERE role = 'coord' AND !project;";
+	setup(&client, read_only).await;
+	let cora = token(&client, "cora", "cora-password").await;
+	let response = client.post(approve).header(bearer(&cora)).dispatch().await;
+	assert_eq!(response.status(), Status::Forbidden);
+	assert_eq!(body(response).await["error"], "missing_permission");
+
+	// the listing only asks for the role
+	let response = client.get("/projects/open/pending").header(bearer(&cora)).dispatch().await;
+	assert_eq!(response.status(), Status::Ok);
+}
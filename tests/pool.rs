@@ -0,0 +1,107 @@
+//! The store handle built straight from the settings, without a server around it
+use std::time::{Duration, Instant};
+
+use rocket::figment::providers::{Format, Toml};
+use rocket::figment::Figment;
+use rocket::tokio::net::TcpListener;
+
+use q_api_auth::app::providers::config::settings::Settings;
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+const SECRET_KEY: &str = "itV5oEbErQuRObmAG2aXwmQHwM4xD/CN1fmxQT3272U=";
+
+/// The shipped `Rocket.toml` in its `local` profile, overridden by `values`
+fn settings(values: &str) -> Settings {
+	let figment = Figment::new()
+		.merge(Toml::file(concat!(env!("CARGO_MANIFEST_DIR"), "/Rocket.toml")).nested())
+		.merge(Toml::string(&format!(r#"secret_key = "{SECRET_KEY}""#)))
+		.merge(Toml::string(values).profile("local"))
+		.select("local");
+
+	Settings::extract(&figment).expect("the configuration should load")
+}
+
+#[rocket::async_test]
+async fn busy_pool_makes_queries_wait_their_turn() {
+	let settings = settings(
+		r#"
+		[databases.store.pool]
+		size = 2
+		"#,
+	);
+	let db = DbAuth::new(&settings).await;
+
+	let started = Instant::now();
+	let sleeps = (0..3).map(|_| {
+		let db = db.clone();
+		rocket::tokio::spawn(
+			async move { db.query("test.sleep", "SLEEP 1s;").await.map(|_| ()) },
+		)
+	});
+	for sleep in sleeps.collect::<Vec<_>>() {
+		sleep.await.expect("query joined").expect("query answered");
+	}
+
+	// the third one waited for a connection to come back
+	let elapsed = started.elapsed();
+	assert!(elapsed >= Duration::from_secs(2), "{elapsed:?}");
+
+	// every connection went back to the pool
+	let burst = (0..16).map(|_| {
+		let db = db.clone();
+		rocket::tokio::spawn(async move { db.read("test.ping", "RETURN 1;").await.map(|_| ()) })
+	});
+	for query in burst.collect::<Vec<_>>() {
+		query.await.expect("query joined").expect("query answered");
+	}
+
+	db.check().await;
+	assert!(db.is_available());
+	assert!(!db.has_read_endpoint());
+}
+
+#[rocket::async_test]
+#[should_panic(expected = "Failed to connect to the database")]
+async fn startup_gives_up_after_its_attempts() {
+	// a port nobody listens on
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("port bound");
+	let port = listener.local_addr().expect("local address").port();
+	drop(listener);
+
+	let settings = settings(&format!(
+		r#"
+		[databases.store]
+		host = "127.0.0.1"
+		port = {port}
+		username = "root"
+		password = "root"
+
+		[databases.store.retry]
+		attempts = 2
+		backoff = 1
+		timeout = 1
+		"#
+	));
+
+	DbAuth::new(&settings).await;
+}
+
+#[rocket::async_test]
+async fn changed_migration_stops_the_migrations() {
+	let tamper = "UPDATE migrations:1 SET checksum = 'edited';";
+
+	let db = DbAuth::new(&settings("")).await;
+	db.query("test.tamper", tamper).await.expect("tamper sent").check().expect("tampered");
+	let error = db.migrate().await.expect_err("changed migration refused");
+	assert!(error.to_string().contains("migration 1 (store)"), "{error}");
+
+	let settings = settings(
+		r#"
+		[databases.store.migrations]
+		allow_changed = true
+		"#,
+	);
+	let db = DbAuth::new(&settings).await;
+	db.query("test.tamper", tamper).await.expect("tamper sent").check().expect("tampered");
+	assert_eq!(db.migrate().await.expect("changed migration allowed"), 0);
+}
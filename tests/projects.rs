@@ -0,0 +1,114 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{json, Value};
+
+use common::{bearer, body, client, get, post, token};
+
+async fn send<'c>(
+	client: &'c Client,
+	uri: &str,
+	admin: &str,
+	body: Value,
+) -> LocalResponse<'c> {
+	client
+		.post(uri.to_owned())
+		.header(bearer(admin))
+		.header(ContentType::JSON)
+		.body(body.to_string())
+		.dispatch()
+		.await
+}
+
+async fn update<'c>(
+	client: &'c Client,
+	id: &str,
+	admin: &str,
+	body: Value,
+) -> LocalResponse<'c> {
+	client
+		.patch(format!("/admin/projects/{id}"))
+		.header(bearer(admin))
+		.header(ContentType::JSON)
+		.body(body.to_string())
+		.dispatch()
+		.await
+}
+
+#[rocket::async_test]
+async fn created_project_takes_signups() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let project = json!({ "name": "Spring", "center": "centers:north", "signup": "open" });
+	let response = send(&client, "/admin/projects", &admin, project).await;
+	assert_eq!(response.status(), Status::Ok);
+	let created = body(response).await;
+	assert_eq!(created["state"], "active");
+	assert_eq!(created["center"], "centers:north");
+	assert_eq!(created["token"].as_str().expect("project secret").len(), 48);
+	let id = created["id"].as_str().expect("project id");
+
+	let credentials = json!({ "username": "bob", "password": "bob-password", "project": id });
+	assert_eq!(post(&client, "/auth/signup", credentials).await.status(), Status::Ok);
+
+	let project = body(get(&client, &format!("/admin/projects/{id}"), &admin).await).await;
+	assert_eq!(project["members"], 1);
+	assert_eq!(project["pending"], 0);
+	// the secret is only given once
+	assert!(project.get("token").is_none());
+}
+
+#[rocket::async_test]
+async fn project_creation_errors() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let cases = [
+		(json!({ "name": "Open", "center": "centers:north" }), Status::Conflict),
+		(json!({ "name": "Spring", "center": "centers:south" }), Status::NotFound),
+		(json!({ "name": "Spring", "center": "projects:open" }), Status::UnprocessableEntity),
+	];
+	for (project, status) in cases {
+		let response = send(&client, "/admin/projects", &admin, project.clone()).await;
+		assert_eq!(response.status(), status, "{project}");
+	}
+}
+
+#[rocket::async_test]
+async fn list_and_update_projects() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let page = body(get(&client, "/admin/projects?center=centers:north", &admin).await).await;
+	assert_eq!(page["total"], 3);
+	let page = body(get(&client, "/admin/projects?center=centers:south", &admin).await).await;
+	assert_eq!(page["total"], 0);
+
+	let changes = json!({ "signup": "closed", "state": "inactive" });
+	let response = update(&client, "projects:open", &admin, changes).await;
+	assert_eq!(response.status(), Status::Ok);
+	let project = body(response).await;
+	assert_eq!(project["name"], "Open");
+	assert_eq!(project["signup"], "closed");
+	assert_eq!(project["state"], "inactive");
+
+	let response = update(&client, "open", &admin, json!({ "name": "Invite" })).await;
+	assert_eq!(response.status(), Status::Conflict);
+	let response = update(&client, "missing", &admin, json!({ "name": "Spring" })).await;
+	assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn projects_are_for_admins() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response = get(&client, "/admin/projects", &alice).await;
+	assert_eq!(response.status(), Status::Forbidden);
+
+	let project = json!({ "name": "Spring", "center": "centers:north" });
+	let response = send(&client, "/admin/projects", &alice, project).await;
+	assert_eq!(response.status(), Status::Forbidden);
+}
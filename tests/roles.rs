@@ -0,0 +1,87 @@
+mod common;
+
+use rocket::http::Status;
+use rocket::serde::json::Value;
+
+use q_api_auth::app::providers::models::user::Role;
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+
+use common::{body, claims, client, get, login, sign, token, SECRET_KEY};
+
+#[test]
+fn roles_rank_from_admin_to_guest() {
+	let ranked = [Role::Admin, Role::Coord, Role::Thera, Role::Parti, Role::Guest];
+	for (i, role) in ranked.iter().enumerate() {
+		for (j, other) in ranked.iter().enumerate() {
+			assert_eq!(role.is_at_least(*other), i <= j, "{role:?} against {other:?}");
+		}
+	}
+
+	// robots stand aside
+	assert!(Role::Robot.is_at_least(Role::Robot));
+	assert!(!Role::Robot.is_at_least(Role::Guest));
+	assert!(!Role::Admin.is_at_least(Role::Robot));
+
+	assert!("boss".parse::<Role>().is_err());
+	assert_eq!("coord".parse::<Role>().ok(), Some(Role::Coord));
+}
+
+#[rocket::async_test]
+async fn guards_answer_with_the_error() {
+	let client = client().await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	// alice again, with another role in a token signed with our key
+	let with_role = |role: Value| {
+		let mut claims = claims(&alice);
+		claims["role"] = role;
+		sign(&claims, SECRET_KEY.as_bytes())
+	};
+
+	let cases = [
+		(alice.clone(), Status::Forbidden, "insufficient_role"),
+		(with_role("robot".into()), Status::Forbidden, "insufficient_role"),
+		(with_role(Value::Null), Status::Forbidden, "missing_role"),
+		(with_role("boss".into()), Status::Unauthorized, "invalid_token"),
+	];
+	for (token, status, error) in cases {
+		let response = get(&client, "/admin/users", &token).await;
+		assert_eq!(response.status(), status, "{error}");
+		assert_eq!(body(response).await["error"], error);
+	}
+
+	let response = client.get("/admin/users").dispatch().await;
+	assert_eq!(body(response).await["error"], "missing_token");
+
+	// above the coordinator is enough for `AtLeast<Coord>`, exactly admin for `RequireRole`
+	let admin = with_role("admin".into());
+	assert_eq!(get(&client, "/invitations", &admin).await.status(), Status::Ok);
+	assert_eq!(get(&client, "/admin/users", &admin).await.status(), Status::Ok);
+	let coord = with_role("coord".into());
+	assert_eq!(get(&client, "/invitations", &coord).await.status(), Status::Ok);
+	assert_eq!(get(&client, "/admin/users", &coord).await.status(), Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn unknown_role_is_skipped_at_login() {
+	let client = client().await;
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	// a role added by a later schema
+	let boss = r#"
+		DEFINE FIELD role ON roled TYPE string;
+		CREATE centers:south SET name = 'South';
+		RELATE users:alice->roled->centers:south SET role = 'boss';
+	"#;
+	db.query("test.unknown_role", boss)
+		.await
+		.expect("role sent")
+		.check()
+		.expect("unknown role");
+
+	let response = login(&client, "alice", "alice-password").await;
+	assert_eq!(response.status(), Status::Ok);
+	let user = body(response).await;
+	assert_eq!(user["role"], "parti");
+	assert_eq!(user["centers"].as_array().expect("centers").len(), 1);
+	assert_eq!(user["centers"][0]["role"], "parti");
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::serde::json::{json, Value};
+
+use common::{bearer, body, client, get, login, token};
+
+async fn put<'c>(client: &'c Client, uri: &str, admin: &str, body: Value) -> LocalResponse<'c> {
+	client
+		.put(uri.to_owned())
+		.header(bearer(admin))
+		.header(ContentType::JSON)
+		.body(body.to_string())
+		.dispatch()
+		.await
+}
+
+fn usernames(page: &Value) -> Vec<&str> {
+	page["items"].as_array().unwrap().iter().map(|u| u["username"].as_str().unwrap()).collect()
+}
+
+#[rocket::async_test]
+async fn list_filters_users() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let page = body(get(&client, "/admin/users", &admin).await).await;
+	assert_eq!(usernames(&page), ["admin", "alice", "sleepy"]);
+	assert_eq!(page["total"], 3);
+
+	let page = body(get(&client, "/admin/users?q=LIC", &admin).await).await;
+	assert_eq!(usernames(&page), ["alice"]);
+	assert_eq!(page["items"][0]["projects"], json!(["projects:open"]));
+	assert_eq!(page["items"][0]["roles"][0]["role"], "parti");
+
+	let page = body(get(&client, "/admin/users?role=parti", &admin).await).await;
+	assert_eq!(usernames(&page), ["alice", "sleepy"]);
+
+	let page = body(get(&client, "/admin/users?project=projects:open", &admin).await).await;
+	assert_eq!(usernames(&page), ["alice"]);
+
+	let uri = "/admin/users?center=centers:north&role=admin";
+	let page = body(get(&client, uri, &admin).await).await;
+	assert_eq!(usernames(&page), ["admin"]);
+
+	let page = body(get(&client, "/admin/users?state=standby", &admin).await).await;
+	assert_eq!(usernames(&page), ["sleepy"]);
+
+	let page = body(get(&client, "/admin/users?per_page=1&page=2", &admin).await).await;
+	assert_eq!(usernames(&page), ["alice"]);
+	assert_eq!(page["total"], 3);
+
+	let response = get(&client, "/admin/users?role=boss", &admin).await;
+	assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn users_are_for_admins() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response = get(&client, "/admin/users/alice", &admin).await;
+	assert_eq!(response.status(), Status::Ok);
+	assert_eq!(body(response).await["id"], "users:alice");
+
+	let response = get(&client, "/admin/users/nobody", &admin).await;
+	assert_eq!(response.status(), Status::NotFound);
+
+	let response = get(&client, "/admin/users", &alice).await;
+	assert_eq!(response.status(), Status::Forbidden);
+
+	let response = client.get("/admin/users").dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[rocket::async_test]
+async fn enabled_user_logs_in_again() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let response =
+		client.post("/admin/users/alice/disable").header(bearer(&admin)).dispatch().await;
+	assert_eq!(body(response).await["disabled"], true);
+	assert_eq!(login(&client, "alice", "alice-password").await.status(), Status::Unauthorized);
+
+	let response =
+		client.post("/admin/users/alice/enable").header(bearer(&admin)).dispatch().await;
+	assert_eq!(body(response).await["disabled"], false);
+	assert_eq!(login(&client, "alice", "alice-password").await.status(), Status::Ok);
+}
+
+#[rocket::async_test]
+async fn password_reset_gives_a_new_password() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+	let alice = token(&client, "alice", "alice-password").await;
+
+	let response = client
+		.post("/admin/users/alice/password-reset")
+		.header(bearer(&admin))
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::Ok);
+	let reset = body(response).await;
+	let password = reset["password"].as_str().expect("new password");
+
+	let response = client.get("/auth/refresh").header(bearer(&alice)).dispatch().await;
+	assert_eq!(response.status(), Status::Unauthorized);
+	assert_eq!(body(response).await["error"], "revoked_session");
+
+	assert_eq!(login(&client, "alice", "alice-password").await.status(), Status::Unauthorized);
+	let response = login(&client, "alice", password).await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let user = body(get(&client, "/admin/users/alice", &admin).await).await;
+	assert_eq!(user["password_reset"], true);
+
+	// the token is only good for changing the password
+	let reset = body(response).await;
+	assert_eq!(reset["password_reset"], true);
+	assert_eq!(reset["perms"], json!([]));
+	assert!(reset["p_token"].is_null());
+	let restricted = reset["g_token"].as_str().expect("global token");
+	let response = client.get("/auth/refresh").header(bearer(restricted)).dispatch().await;
+	assert_eq!(response.status(), Status::Forbidden);
+	assert_eq!(body(response).await["error"], "password_reset");
+
+	let change = json!({ "password": password, "new_password": "alice-new" });
+	let response = client
+		.post("/auth/password")
+		.header(bearer(restricted))
+		.header(ContentType::JSON)
+		.body(change.to_string())
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::NoContent);
+
+	let user = body(login(&client, "alice", "alice-new").await).await;
+	assert_eq!(user["password_reset"], false);
+	assert!(user["p_token"].as_str().is_some());
+
+	let response = client
+		.post("/admin/users/nobody/password-reset")
+		.header(bearer(&admin))
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn user_moves_to_another_project() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let project = json!({ "project": "projects:invite" });
+	let response = put(&client, "/admin/users/alice/project", &admin, project).await;
+	assert_eq!(response.status(), Status::Ok);
+	let user = body(response).await;
+	assert_eq!(user["project"], "projects:invite");
+	assert_eq!(user["projects"], json!(["projects:invite"]));
+	assert_eq!(user["roles"].as_array().unwrap().len(), 1);
+
+	let project = json!({ "project": "projects:missing" });
+	let response = put(&client, "/admin/users/alice/project", &admin, project).await;
+	assert_eq!(response.status(), Status::NotFound);
+}
+
+#[rocket::async_test]
+async fn state_changes_are_kept() {
+	let client = client().await;
+	let admin = token(&client, "admin", "admin-password").await;
+
+	let change = json!({ "state": "exited", "reason": "moved away" });
+	let response = put(&client, "/admin/users/alice/state", &admin, change).await;
+	assert_eq!(response.status(), Status::Ok);
+	assert_eq!(body(response).await["state"], "exited");
+
+	// the same state again is no change
+	let change = json!({ "state": "exited" });
+	put(&client, "/admin/users/alice/state", &admin, change).await;
+	let change = json!({ "state": "active" });
+	put(&client, "/admin/users/alice/state", &admin, change).await;
+
+	let states = body(get(&client, "/admin/users/alice/states", &admin).await).await;
+	let states = states.as_array().expect("state changes");
+	assert_eq!(states.len(), 2);
+	assert_eq!(states[0]["previous"], "exited");
+	assert_eq!(states[0]["state"], "active");
+	assert_eq!(states[1]["reason"], "moved away");
+	assert_eq!(states[1]["by"], "users:admin");
+
+	let response = get(&client, "/admin/users/nobody/states", &admin).await;
+	assert_eq!(response.status(), Status::NotFound);
+}
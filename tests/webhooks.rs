@@ -0,0 +1,277 @@
+mod common;
+
+use std::time::Duration;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{json, Value};
+use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use rocket::tokio::net::TcpListener;
+use rocket::tokio::sync::mpsc;
+use rocket::tokio::time::timeout;
+
+use q_api_auth::app::providers::services::auth::db::DbAuth;
+use q_api_auth::app::providers::services::webhooks::signature;
+
+use common::{bearer, body, client, get, login, token};
+
+/// What the receiver got, header names in lowercase
+struct Received {
+	headers: Vec<(String, String)>,
+	body: String,
+}
+
+impl Received {
+	fn header(&self, name: &str) -> Option<&str> {
+		self.headers
+			.iter()
+			.find(|(key, _)| key.eq_ignore_ascii_case(name))
+			.map(|(_, value)| value.as_str())
+	}
+}
+
+/// Stand-in for a subscriber, answers every request with `status`
+async fn receiver(status: u16) -> (String, mpsc::UnboundedReceiver<Received>) {
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("port bound");
+	let url = format!("http://{}/hook", listener.local_addr().expect("local address"));
+	let (sender, received) = mpsc::unbounded_channel();
+
+	rocket::tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut request = Vec::new();
+			let mut buffer = [0; 4096];
+
+			// the headers, then as much body as they announce
+			let (head, length) = loop {
+				let read = stream.read(&mut buffer).await.unwrap_or(0);
+				if read == 0 {
+					break (String::new(), 0);
+				}
+				request.extend_from_slice(&buffer[..read]);
+
+				let text = String::from_utf8_lossy(&request).into_owned();
+				if let Some(end) = text.find("\r\n\r\n") {
+					let head = text[..end].to_owned();
+					let length = head
+						.lines()
+						.filter_map(|line| line.split_once(':'))
+						.find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+						.and_then(|(_, value)| value.trim().parse::<usize>().ok())
+						.unwrap_or(0);
+
+					while request.len() < end + 4 + length {
+						let read = stream.read(&mut buffer).await.unwrap_or(0);
+						if read == 0 {
+							break;
+						}
+						request.extend_from_slice(&buffer[..read]);
+					}
+
+					break (head, end + 4);
+				}
+			};
+
+			let headers = head
+				.lines()
+				.skip(1)
+				.filter_map(|line| line.split_once(':'))
+				.map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_owned()))
+				.collect();
+			let body = request.get(length..).unwrap_or_default();
+			let body = String::from_utf8_lossy(body).into_owned();
+
+			let answer = format!(
+				"HTTP/1.1 {status} Stand-in\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+			);
+			let _ = stream.write_all(answer.as_bytes()).await;
+			let _ = sender.send(Received {
+				headers,
+				body,
+			});
+		}
+	});
+
+	(url, received)
+}
+
+#[rocket::async_test]
+async fn login_event_is_delivered_signed() {
+	let client = client().await;
+	let (url, mut received) = receiver(200).await;
+
+	let admin = token(&client, "admin", "admin-password").await;
+	let subscription =
+		json!({ "url": url, "events": ["auth.login"], "secret": "shared-secret" });
+	let response = client
+		.post("/admin/webhooks")
+		.header(bearer(&admin))
+		.header(ContentType::JSON)
+		.body(subscription.to_string())
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::Ok);
+	assert_eq!(body(response).await["secret"], "shared-secret");
+
+	assert_eq!(login(&client, "alice", "alice-password").await.status(), Status::Ok);
+
+	let delivery = timeout(Duration::from_secs(10), received.recv())
+		.await
+		.expect("delivered in time")
+		.expect("receiver running");
+
+	assert_eq!(delivery.header("x-webhook-event"), Some("auth.login"));
+
+	let timestamp: i64 = delivery
+		.header("x-webhook-timestamp")
+		.and_then(|timestamp| timestamp.parse().ok())
+		.expect("timestamp header");
+	let expected = signature::sign("shared-secret", timestamp, &delivery.body);
+	assert_eq!(delivery.header("x-webhook-signature"), Some(expected.as_str()));
+
+	let event: Value = rocket::serde::json::from_str(&delivery.body).expect("json event");
+	assert_eq!(event["event"], "auth.login");
+	assert_eq!(event["user"], "users:alice");
+}
+
+#[rocket::async_test]
+async fn failed_delivery_is_retried() {
+	let client = client().await;
+	let (url, mut received) = receiver(500).await;
+
+	let admin = token(&client, "admin", "admin-password").await;
+	// pings are sent whatever the subscription listens to
+	let subscription = json!({ "url": url, "events": ["user.disable"] });
+	let response = client
+		.post("/admin/webhooks")
+		.header(bearer(&admin))
+		.header(ContentType::JSON)
+		.body(subscription.to_string())
+		.dispatch()
+		.await;
+	assert_eq!(response.status(), Status::Ok);
+
+	let id = body(response).await["id"].as_str().expect("webhook id").to_owned();
+	let ping = format!("/admin/webhooks/{id}/ping");
+	let response = client.post(ping).header(bearer(&admin)).dispatch().await;
+	assert!(response.status().class().is_success());
+
+	for _ in 0..2 {
+		let delivery = timeout(Duration::from_secs(10), received.recv())
+			.await
+			.expect("attempted in time")
+			.expect("receiver running");
+		assert_eq!(delivery.header("x-webhook-event"), Some("ping"));
+	}
+}
+
+/// Waits for the worker to move the deliveries of `webhook` to the dead letters
+async fn dead(client: &Client, admin: &str, webhook: &str) -> Value {
+	let uri = format!("/admin/webhooks/dead?webhook={webhook}");
+
+	for _ in 0..20 {
+		let page = body(get(client, &uri, admin).await).await;
+		if page["total"] != 0 {
+			return page;
+		}
+		rocket::tokio::time::sleep(Duration::from_millis(500)).await;
+	}
+
+	panic!("no dead delivery for {webhook}");
+}
+
+#[rocket::async_test]
+async fn spent_delivery_goes_to_the_dead_letters() {
+	let client = client().await;
+	let (url, mut received) = receiver(500).await;
+
+	let admin = token(&client, "admin", "admin-password").await;
+	let subscription = json!({ "url": url, "events": ["auth.login"] });
+	let response = client
+		.post("/admin/webhooks")
+		.header(bearer(&admin))
+		.header(ContentType::JSON)
+		.body(subscription.to_string())
+		.dispatch()
+		.await;
+	let id = body(response).await["id"].as_str().expect("webhook id").to_owned();
+
+	// one attempt short of the limit, the next failure is the last
+	let db = client.rocket().state::<DbAuth>().expect("database managed");
+	db.query(
+		"test.spend",
+		r#"
+		CREATE webhook_deliveries CONTENT {
+			webhook: <record> $b_webhook, event: 'ping', body: '{}', attempts: 7,
+			next_at: time::now(), created_at: time::now(),
+		};
+		"#,
+	)
+	.bind(("b_webhook", &id))
+	.await
+	.expect("delivery sent")
+	.check()
+	.expect("delivery queued");
+
+	let page = dead(&client, &admin, &id).await;
+	timeout(Duration::from_secs(1), received.recv()).await.expect("attempted once");
+	assert_eq!(page["total"], 1);
+	assert_eq!(page["items"][0]["attempts"], 8);
+	assert_eq!(page["items"][0]["error"], "receiver answered 500 Internal Server Error");
+
+	// back in the queue for another round of attempts
+	let dead_id = page["items"][0]["id"].as_str().expect("dead delivery id");
+	let retry = format!("/admin/webhooks/dead/{dead_id}/retry");
+	let response = client.post(retry).header(bearer(&admin)).dispatch().await;
+	assert!(response.status().class().is_success());
+
+	let delivery = timeout(Duration::from_secs(10), received.recv())
+		.await
+		.expect("retried in time")
+		.expect("receiver running");
+	assert_eq!(delivery.header("x-webhook-event"), Some("ping"));
+}
+
+#[rocket::async_test]
+async fn deliveries_of_a_removed_webhook_are_dead() {
+	let client = client().await;
+	let (url, _received) = receiver(500).await;
+
+	let admin = token(&client, "admin", "admin-password").await;
+	let subscription = json!({ "url": url, "events": ["auth.login"] });
+	let response = client
+		.post("/admin/webhooks")
+		.header(bearer(&admin))
+		.header(ContentType::JSON)
+		.body(subscription.to_string())
+		.dispatch()
+		.await;
+	let id = body(response).await["id"].as_str().expect("webhook id").to_owned();
+
+	let ping = format!("/admin/webhooks/{id}/ping");
+	client.post(ping).header(bearer(&admin)).dispatch().await;
+	let remove = format!("/admin/webhooks/{id}");
+	let response = client.delete(remove).header(bearer(&admin)).dispatch().await;
+	assert!(response.status().class().is_success());
+
+	let page = dead(&client, &admin, &id).await;
+	assert_eq!(page["items"][0]["event"], "ping");
+
+	// with nowhere to go it stays dead
+	let dead_id = page["items"][0]["id"].as_str().expect("dead delivery id");
+	let retry = format!("/admin/webhooks/dead/{dead_id}/retry");
+	let response = client.post(retry).header(bearer(&admin)).dispatch().await;
+	assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn signature_is_the_hmac_of_timestamp_and_body() {
+	let signature = signature::sign("shared-secret", 1700000000, r#"{"event":"ping"}"#);
+	assert_eq!(
+		signature,
+		"sha256=c8f2edd2a8ab1c95e709ca09285f9a6e9a00bb0d05d947244c5397324c316d86"
+	);
+
+	// a receiver with another secret, or a replayed timestamp, gets another one
+	assert_ne!(signature::sign("other-secret", 1700000000, r#"{"event":"ping"}"#), signature);
+	assert_ne!(signature::sign("shared-secret", 1700000001, r#"{"event":"ping"}"#), signature);
+}